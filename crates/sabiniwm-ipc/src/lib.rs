@@ -0,0 +1,304 @@
+//! # sabiniwm-ipc: IPC protocol of sabiniwm
+//!
+//! sabiniwm listens on a Unix socket whose path is exported as `$SABINIWM_SOCKET` to child processes. A client sends
+//! [`Request`]s as line-delimited JSON and the server replies one [`Response`] line per request.
+//!
+//! After [`Request::Subscribe`] is replied, the connection is dedicated to the subscription and the server sends
+//! [`Event`] lines.
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+/// Environment variable that holds the path of the IPC socket.
+pub const SOCKET_ENV: &str = "SABINIWM_SOCKET";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Dumps the current `StackSet`.
+    GetStackSet,
+    /// Executes an action as if it is triggered by a key binding.
+    Dispatch { action: Action },
+    /// Subscribes events. The server replies `Response::Ok` and then sends `Event`s.
+    Subscribe,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Spawn {
+        command: String,
+    },
+    WorkspaceFocus {
+        target: WorkspaceTarget,
+    },
+    WorkspaceGreedyFocus {
+        target: WorkspaceTarget,
+    },
+    WindowMoveToWorkspace {
+        target: WorkspaceTarget,
+    },
+    ScreenFocus {
+        direction: Direction,
+    },
+    ScreenSwap {
+        direction: Direction,
+    },
+    WindowMoveToScreen {
+        direction: Direction,
+    },
+    MoveFocus {
+        direction: Direction,
+    },
+    WindowSwap {
+        direction: Direction,
+    },
+    WindowKill,
+    WindowFloat,
+    WindowSink,
+    WindowMinimize,
+    /// Restores the window with the id, or the most recently minimized window in the focused workspace if `None`.
+    WindowRestore {
+        id: Option<u64>,
+    },
+    LayoutMessage {
+        message: LayoutMessage,
+    },
+    WorkspaceResetLayout,
+    WorkspaceAdd {
+        tag: String,
+    },
+    WorkspaceRename {
+        tag: String,
+        new_tag: String,
+    },
+    /// Deletes a workspace. It must be empty unless `move_windows_to` is given.
+    WorkspaceDelete {
+        tag: String,
+        move_windows_to: Option<String>,
+    },
+    WorkspaceMove {
+        tag: String,
+        index: usize,
+    },
+    ScratchpadToggle {
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Next,
+    Prev,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceTarget {
+    Next,
+    Prev,
+    Tag(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutMessage {
+    SelectNext,
+    SelectPrev,
+    Toggle,
+    IncMaster { delta: i32 },
+    Shrink,
+    Expand,
+    Reset,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    StackSet { stackset: StackSet },
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    WorkspaceFocusChanged {
+        tag: String,
+    },
+    WindowMapped {
+        window: Window,
+    },
+    WindowUnmapped {
+        id: u64,
+    },
+    WindowFocusChanged {
+        id: Option<u64>,
+    },
+    WindowTitleChanged {
+        id: u64,
+        title: Option<String>,
+    },
+    /// Layout of the workspace is changed by a layout message.
+    LayoutChanged {
+        workspace: String,
+        /// Description of the current layout, e.g. "Tall".
+        layout: String,
+    },
+    SessionLocked,
+    SessionUnlocked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackSet {
+    pub workspaces: Vec<Workspace>,
+    pub screens: Vec<Screen>,
+    /// Bottom to top.
+    pub float_windows: Vec<FloatWindow>,
+    pub focused_window: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub tag: String,
+    pub focused: bool,
+    pub visible: bool,
+    pub stack: Vec<Window>,
+    /// Focused window in the stack, which is not necessarily focused globally.
+    pub stack_focus: Option<u64>,
+    /// Description of the current layout, e.g. "Tall".
+    pub layout: String,
+    /// Minimized windows, oldest first.
+    pub minimized: Vec<Window>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Screen {
+    pub output: String,
+    pub rect: Rect,
+    pub workspace: String,
+    pub focused: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Window {
+    pub id: u64,
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FloatWindow {
+    #[serde(flatten)]
+    pub window: Window,
+    pub geometry: Rect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+/// A blocking client.
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    /// Connects to the socket designated by `$SABINIWM_SOCKET`.
+    pub fn connect() -> std::io::Result<Self> {
+        let path = std::env::var_os(SOCKET_ENV).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("${} is not set", SOCKET_ENV),
+            )
+        })?;
+        Self::connect_to(path)
+    }
+
+    pub fn connect_to(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let writer = UnixStream::connect(path)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(Self { reader, writer })
+    }
+
+    pub fn request(&mut self, request: &Request) -> std::io::Result<Response> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+
+        self.read_line()
+    }
+
+    /// Subscribes events. The connection can't be used for other requests after that.
+    pub fn subscribe(mut self) -> std::io::Result<Subscription> {
+        match self.request(&Request::Subscribe)? {
+            Response::Ok => Ok(Subscription { client: self }),
+            response => Err(std::io::Error::other(format!(
+                "unexpected response: {:?}",
+                response
+            ))),
+        }
+    }
+
+    fn read_line<T>(&mut self) -> std::io::Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+pub struct Subscription {
+    client: Client,
+}
+
+impl Iterator for Subscription {
+    type Item = std::io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.client.read_line() {
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => None,
+            ret => Some(ret),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_json() {
+        let request = Request::Dispatch {
+            action: Action::WorkspaceFocus {
+                target: WorkspaceTarget::Tag("1".to_string()),
+            },
+        };
+        let json =
+            r#"{"type":"dispatch","action":{"type":"workspace_focus","target":{"tag":"1"}}}"#;
+        assert_eq!(serde_json::to_string(&request).unwrap(), json);
+        assert_eq!(serde_json::from_str::<Request>(json).unwrap(), request);
+
+        let json = r#"{"type":"dispatch","action":{"type":"move_focus","direction":"next"}}"#;
+        assert_eq!(
+            serde_json::from_str::<Request>(json).unwrap(),
+            Request::Dispatch {
+                action: Action::MoveFocus {
+                    direction: Direction::Next
+                }
+            }
+        );
+    }
+}
@@ -11,7 +11,9 @@ use sabiniwm::action::{self, Action, ActionFnI};
 use sabiniwm::config::{ConfigDelegateUnstableI, XkbConfig};
 use sabiniwm::input::{KeySeqSerde, Keymap, ModMask};
 use sabiniwm::reexports::smithay;
-use sabiniwm::view::predefined::{LayoutMessageSelect, LayoutMessageToggle};
+use sabiniwm::view::predefined::{
+    LayoutMessageIncMaster, LayoutMessageResize, LayoutMessageSelect, LayoutMessageToggle,
+};
 use sabiniwm::view::stackset::WorkspaceTag;
 use sabiniwm::SabiniwmState;
 
@@ -127,6 +129,10 @@ impl ConfigDelegateUnstableI for Config {
             kbd("H-space") => LayoutMessageSelect::Next.into(),
             // Toggle Full
             kbd("H-f") => LayoutMessageToggle.into(),
+            kbd("H-comma") => LayoutMessageIncMaster(1).into(),
+            kbd("H-period") => LayoutMessageIncMaster(-1).into(),
+            kbd("H-bracketleft") => LayoutMessageResize::Shrink.into(),
+            kbd("H-bracketright") => LayoutMessageResize::Expand.into(),
 
             kbd("H-d") => action::ActionWorkspaceFocusNonEmpty::Prev.into_action(),
             kbd("H-h") => action::ActionMoveFocus::Prev.into_action(),
@@ -148,6 +154,10 @@ impl ConfigDelegateUnstableI for Config {
             kbd("H-v") => action::ActionWorkspaceFocus::Next.into_action(),
             kbd("H-b") => action::ActionWorkspaceFocus::Prev.into_action(),
 
+            kbd("H-w") => action::ActionScreenFocus::Next.into_action(),
+            kbd("H-W") => action::ActionWindowMoveToScreen::Next.into_action(),
+            kbd("H-m") => action::ActionScreenSwap::Next.into_action(),
+
             kbd("H-k") => (action::ActionWindowKill {}).into_action(),
 
             kbd("H-r") => (action::ActionWindowFloat {}).into_action(),
@@ -237,7 +247,9 @@ impl ConfigDelegateUnstableI for Config {
 
         fn do_center_float(stackset: &mut StackSet, wq: &WindowQuery, ratio: (f32, f32, f32, f32)) {
             // Use size = surface size or shrinked by ratio
-            let mut rect = *wq.get_primary_output_rect();
+            let Some(&(mut rect)) = wq.get_primary_output_rect() else {
+                return;
+            };
             let size = if let Some(size) = wq.surface_size() {
                 size
             } else {
@@ -86,6 +86,12 @@ impl SabiniwmState {
                 self.inner
                     .view
                     .handle_layout_message(message, &mut self.inner.space);
+                let workspace = self.inner.view.stackset().workspaces().focus();
+                let event = sabiniwm_ipc::Event::LayoutChanged {
+                    workspace: workspace.tag.0.clone(),
+                    layout: workspace.layout_description(),
+                };
+                self.inner.emit_ipc_event(event);
                 self.reflect_focus_from_stackset();
             }
             Action::ActionFn(f) => {
@@ -1,7 +1,10 @@
 use crate::action::action::{Action, ActionFnI};
 use crate::backend::BackendI;
+use crate::config::ConfigDelegateUnstableI;
 use crate::state::SabiniwmState;
+use crate::util::Id;
 use crate::view::stackset::WorkspaceTag;
+use crate::view::window::Window;
 
 #[derive(Debug, Clone)]
 pub struct ActionWithSavedFocus(pub Action);
@@ -88,6 +91,9 @@ impl ActionFnI for ActionWindowSwap {
     }
 }
 
+/// Focuses a workspace with xmonad's `view` semantics.
+///
+/// If the workspace is visible on another screen, focus moves to that screen. See also `ActionWorkspaceGreedyFocus`.
 #[derive(Debug, Clone)]
 pub enum ActionWorkspaceFocus {
     Next,
@@ -103,13 +109,13 @@ impl ActionFnI for ActionWorkspaceFocus {
             Self::WithTag(tag) => {
                 let ss = state.inner.view.stackset();
                 let src = ss.workspaces.focused_index();
-                // TODO: Error handling.
-                let dst = ss
-                    .workspaces
-                    .as_vec()
-                    .iter()
-                    .position(|ws| ws.tag == *tag)
-                    .expect("workspace with the given tag exists");
+                let dst = match ss.workspace_index(tag) {
+                    Ok(dst) => dst,
+                    Err(e) => {
+                        warn!("{}", e);
+                        return;
+                    }
+                };
                 dst as isize - src as isize
             }
         };
@@ -121,6 +127,141 @@ impl ActionFnI for ActionWorkspaceFocus {
     }
 }
 
+/// Focuses a workspace with xmonad's `greedyView` semantics.
+///
+/// `ActionWorkspaceFocus` has `view` semantics, i.e. it focuses the screen showing the workspace if it is visible. This
+/// shows the workspace on the focused screen, swapping workspaces of the two screens if necessary.
+#[derive(Debug, Clone)]
+pub enum ActionWorkspaceGreedyFocus {
+    Next,
+    Prev,
+    WithTag(WorkspaceTag),
+}
+
+impl ActionFnI for ActionWorkspaceGreedyFocus {
+    fn exec(&self, state: &mut SabiniwmState) {
+        state.inner.view.update_stackset_with(|stackset| {
+            let workspaces = &stackset.workspaces;
+            let i = match self {
+                Self::Next => workspaces.mod_plus_focused_index(1),
+                Self::Prev => workspaces.mod_plus_focused_index(-1),
+                Self::WithTag(tag) => match stackset.workspace_index(tag) {
+                    Ok(i) => i,
+                    Err(e) => {
+                        warn!("{}", e);
+                        return;
+                    }
+                },
+            };
+            stackset.greedy_view(i);
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ActionScreenFocus {
+    Next,
+    Prev,
+}
+
+impl ActionFnI for ActionScreenFocus {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let count = match self {
+            Self::Next => 1,
+            Self::Prev => -1,
+        };
+        state.inner.view.update_stackset_with(|stackset| {
+            if stackset.screens.is_empty() {
+                return;
+            }
+
+            let i = stackset.screens.mod_plus_focused_index(count);
+            stackset.focus_screen(i);
+        });
+    }
+}
+
+/// Swaps workspaces shown on the focused screen and the next/previous screen. Screen focus is kept.
+#[derive(Debug, Clone)]
+pub enum ActionScreenSwap {
+    Next,
+    Prev,
+}
+
+impl ActionFnI for ActionScreenSwap {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let count = match self {
+            Self::Next => 1,
+            Self::Prev => -1,
+        };
+        state.inner.view.update_stackset_with(|stackset| {
+            if stackset.screens.is_empty() {
+                return;
+            }
+
+            let i = stackset.screens.mod_plus_focused_index(count);
+            let workspace_index = stackset.screens.as_vec()[i].workspace_index;
+            stackset.greedy_view(workspace_index);
+        });
+    }
+}
+
+/// Moves the focused window to the workspace shown on the next/previous screen. Focus follows the window.
+#[derive(Debug, Clone)]
+pub enum ActionWindowMoveToScreen {
+    Next,
+    Prev,
+}
+
+impl ActionFnI for ActionWindowMoveToScreen {
+    fn exec(&self, state: &mut SabiniwmState) {
+        use crate::view::stackset::WindowFocusType;
+
+        let count = match self {
+            Self::Next => 1,
+            Self::Prev => -1,
+        };
+        state.inner.view.update_stackset_with(|stackset| {
+            if stackset.screens.len() < 2 {
+                return;
+            }
+
+            let src_screen_index = stackset.screens.focused_index();
+            let dst_screen_index = stackset.screens.mod_plus_focused_index(count);
+            let src_rect = stackset.screens.as_vec()[src_screen_index].rect;
+            let dst_rect = stackset.screens.as_vec()[dst_screen_index].rect;
+            let dst_workspace_index = stackset.screens.as_vec()[dst_screen_index].workspace_index;
+
+            match stackset.window_focus_type {
+                WindowFocusType::Stack => {
+                    let mut workspaces = stackset.workspaces.as_mut();
+
+                    let mut src = workspaces.vec[workspaces.focus].stack.as_mut();
+                    if src.vec.is_empty() {
+                        return;
+                    }
+                    let window = src.vec.remove(src.focus);
+                    src.focus = src.focus.min(src.vec.len().saturating_sub(1));
+                    src.commit();
+
+                    let dst = workspaces.vec[dst_workspace_index].stack.as_mut();
+                    dst.vec.insert(dst.focus, window);
+                    dst.commit();
+
+                    workspaces.commit();
+                }
+                WindowFocusType::Float => {
+                    // Float windows are not bound to workspaces. Translate it so that it keeps the relative position.
+                    let fw = stackset.float_windows.last_mut().unwrap(/* must not be empty as WindowFocusType is Float */);
+                    fw.geometry.loc += dst_rect.loc - src_rect.loc;
+                }
+            }
+
+            stackset.focus_screen(dst_screen_index);
+        });
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ActionWorkspaceFocusNonEmpty {
     Next,
@@ -161,13 +302,13 @@ impl ActionFnI for ActionWindowMoveToWorkspace {
             Self::WithTag(tag) => {
                 let ss = state.inner.view.stackset();
                 let src = ss.workspaces.focused_index();
-                // TODO: Error handling.
-                let dst = ss
-                    .workspaces
-                    .as_vec()
-                    .iter()
-                    .position(|ws| ws.tag == *tag)
-                    .expect("workspace with the given tag exists");
+                let dst = match ss.workspace_index(tag) {
+                    Ok(dst) => dst,
+                    Err(e) => {
+                        warn!("{}", e);
+                        return;
+                    }
+                };
                 dst as isize - src as isize
             }
         };
@@ -190,23 +331,122 @@ impl ActionFnI for ActionWindowMoveToWorkspace {
     }
 }
 
+/// Resets the layout of the focused workspace to the initial state, like xmonad's `setLayout`.
+#[derive(Debug, Clone)]
+pub struct ActionWorkspaceResetLayout {}
+
+impl ActionFnI for ActionWorkspaceResetLayout {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let event = state.inner.view.update_stackset_with(|stackset| {
+            let workspace = stackset.workspaces.focus_mut();
+            workspace.reset_layout_tree();
+            sabiniwm_ipc::Event::LayoutChanged {
+                workspace: workspace.tag.0.clone(),
+                layout: workspace.layout_description(),
+            }
+        });
+        state.inner.emit_ipc_event(event);
+    }
+}
+
+/// Adds a workspace at the end with the layout given by `make_layout_tree_builder_for_workspace()`.
+#[derive(Debug, Clone)]
+pub struct ActionWorkspaceAdd(pub WorkspaceTag);
+
+impl ActionFnI for ActionWorkspaceAdd {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let layout_tree_builder = state
+            .inner
+            .config_delegate
+            .make_layout_tree_builder_for_workspace(&self.0);
+        let result = state.inner.view.update_stackset_with(|stackset| {
+            stackset.add_workspace(self.0.clone(), layout_tree_builder)
+        });
+        if let Err(e) = result {
+            warn!("{}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActionWorkspaceRename {
+    pub tag: WorkspaceTag,
+    pub new_tag: WorkspaceTag,
+}
+
+impl ActionFnI for ActionWorkspaceRename {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let result = state.inner.view.update_stackset_with(|stackset| {
+            stackset.rename_workspace(&self.tag, self.new_tag.clone())
+        });
+        if let Err(e) = result {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// Deletes a workspace. See `StackSet::delete_workspace()`.
+#[derive(Debug, Clone)]
+pub struct ActionWorkspaceDelete {
+    pub tag: WorkspaceTag,
+    pub move_windows_to: Option<WorkspaceTag>,
+}
+
+impl ActionFnI for ActionWorkspaceDelete {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let result = state.inner.view.update_stackset_with(|stackset| {
+            stackset.delete_workspace(&self.tag, self.move_windows_to.as_ref())
+        });
+        if let Err(e) = result {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// Moves a workspace to the index, shifting the others.
+#[derive(Debug, Clone)]
+pub struct ActionWorkspaceMove {
+    pub tag: WorkspaceTag,
+    pub index: usize,
+}
+
+impl ActionFnI for ActionWorkspaceMove {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let result = state
+            .inner
+            .view
+            .update_stackset_with(|stackset| stackset.move_workspace(&self.tag, self.index));
+        if let Err(e) = result {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// Toggles a named scratchpad given by `make_scratchpads()`, like xmonad's `namedScratchpadAction`.
+///
+/// It spawns the command if the scratchpad has no window. Otherwise, it shows the window as a float window on the
+/// focused screen, or hides it if it is shown.
+#[derive(Debug, Clone)]
+pub struct ActionScratchpadToggle(pub String);
+
+impl ActionFnI for ActionScratchpadToggle {
+    fn exec(&self, state: &mut SabiniwmState) {
+        if let Some(command) = state.inner.view.toggle_scratchpad(&self.0) {
+            state.process_action(&Action::Spawn(command));
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ActionWindowKill {}
 
 impl ActionFnI for ActionWindowKill {
     fn exec(&self, state: &mut SabiniwmState) {
-        use smithay::desktop::WindowSurface;
-
         let Some(window) = state.inner.view.focused_window_mut() else {
             return;
         };
 
-        match window.smithay_window().underlying_surface() {
-            WindowSurface::Wayland(w) => w.send_close(),
-            WindowSurface::X11(w) => {
-                let _ = w.close();
-            }
-        };
+        window.close();
     }
 }
 
@@ -251,6 +491,52 @@ impl ActionFnI for ActionWindowSink {
     }
 }
 
+/// Minimizes the focused window. See `StackSet::minimize_window()`.
+#[derive(Debug, Clone)]
+pub struct ActionWindowMinimize {}
+
+impl ActionFnI for ActionWindowMinimize {
+    fn exec(&self, state: &mut SabiniwmState) {
+        let Some(window) = state.inner.view.focused_window() else {
+            return;
+        };
+        let window_id = window.id();
+        state
+            .inner
+            .view
+            .update_stackset_with(|stackset| stackset.minimize_window(window_id));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ActionWindowRestore {
+    /// The most recently minimized window in the focused workspace.
+    Last,
+    Window(Id<Window>),
+}
+
+impl ActionFnI for ActionWindowRestore {
+    fn exec(&self, state: &mut SabiniwmState) {
+        match self {
+            ActionWindowRestore::Last => {
+                state
+                    .inner
+                    .view
+                    .update_stackset_with(|stackset| stackset.restore_last_window());
+            }
+            ActionWindowRestore::Window(window_id) => {
+                let restored = state
+                    .inner
+                    .view
+                    .update_stackset_with(|stackset| stackset.restore_window(*window_id));
+                if !restored {
+                    warn!("window is not minimized: id = {:?}", window_id);
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ActionSequential(pub Vec<Action>);
 
@@ -0,0 +1,303 @@
+//! Backend without GPU, display server nor input devices, for testing and servers.
+//!
+//! It creates virtual outputs with sizes given by `$SABINIWM_HEADLESS_OUTPUTS` and renders them with the pixman
+//! renderer, or doesn't render at all if `$SABINIWM_HEADLESS_RENDER=false`. In both cases, frame callbacks are sent
+//! periodically so that clients keep running.
+//!
+//! Screen capture protocols are not provided on this backend as they render with GLES.
+
+use crate::backend::BackendI;
+use crate::envvar::EnvVar;
+use crate::render::OutputRenderElement;
+use crate::render_loop::RenderLoop;
+use crate::state::{InnerState, SabiniwmState, SabiniwmStateWithConcreteBackend};
+use crate::view::window::WindowRenderElement;
+use crate::wl_global::WlGlobal;
+use eyre::WrapErr;
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::damage::OutputDamageTracker;
+use smithay::backend::renderer::element::RenderElementStates;
+use smithay::backend::renderer::pixman::PixmanRenderer;
+use smithay::backend::renderer::{Bind, Color32F, ImportDma, ImportMemWl, Offscreen};
+use smithay::output::{Mode, PhysicalProperties, Subpixel};
+use smithay::reexports::calloop::LoopHandle;
+use smithay::reexports::pixman;
+use smithay::reexports::wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
+use smithay::reexports::wayland_server;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::protocol::wl_surface;
+use smithay::utils::{Physical, Size, Transform};
+use std::time::Duration;
+
+const REFRESH_RATE: i32 = 60_000;
+
+pub(crate) struct HeadlessBackend {
+    /// `None` if rendering is disabled.
+    renderer: Option<PixmanRenderer>,
+    outputs: Vec<HeadlessOutput>,
+    dmabuf_state: smithay::wayland::dmabuf::DmabufState,
+}
+
+struct HeadlessOutput {
+    output: smithay::output::Output,
+    render_loop: RenderLoop<SabiniwmState>,
+    damage_tracker: OutputDamageTracker,
+    /// Offscreen buffer that the output is rendered into.
+    buffer: Option<pixman::Image<'static, 'static>>,
+    /// Set in `BackendI::init()`. Holds not to `drop()`.
+    #[allow(unused)]
+    wl_output_global: Option<WlGlobal<SabiniwmState, WlOutput>>,
+}
+
+/// Parses output sizes, e.g. "1920x1080,1280x720".
+fn parse_output_sizes(s: &str) -> eyre::Result<Vec<Size<i32, Physical>>> {
+    s.split(',')
+        .map(|size| {
+            let (w, h) = size
+                .trim()
+                .split_once('x')
+                .ok_or_else(|| eyre::eyre!("invalid output size: {}", size))?;
+            let w = w
+                .parse::<i32>()
+                .wrap_err_with(|| format!("invalid output size: {}", size))?;
+            let h = h
+                .parse::<i32>()
+                .wrap_err_with(|| format!("invalid output size: {}", size))?;
+            eyre::ensure!(w > 0 && h > 0, "invalid output size: {}", size);
+            Ok(Size::from((w, h)))
+        })
+        .collect()
+}
+
+impl HeadlessBackend {
+    pub(crate) fn new(
+        envvar: &EnvVar,
+        loop_handle: LoopHandle<'static, SabiniwmState>,
+    ) -> eyre::Result<Self> {
+        let renderer = if envvar.sabiniwm.headless_render {
+            Some(
+                PixmanRenderer::new()
+                    .map_err(|e| eyre::eyre!("{}", e))
+                    .wrap_err("initializing pixman renderer")?,
+            )
+        } else {
+            None
+        };
+
+        let sizes = parse_output_sizes(&envvar.sabiniwm.headless_outputs)?;
+        let mut x = 0;
+        let outputs = sizes
+            .into_iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let output = smithay::output::Output::new(
+                    format!("HEADLESS-{}", i + 1),
+                    PhysicalProperties {
+                        size: (0, 0).into(),
+                        subpixel: Subpixel::Unknown,
+                        make: "Smithay".into(),
+                        model: "Headless".into(),
+                    },
+                );
+                let mode = Mode {
+                    size,
+                    refresh: REFRESH_RATE,
+                };
+                output.change_current_state(
+                    Some(mode),
+                    Some(Transform::Normal),
+                    None,
+                    Some((x, 0).into()),
+                );
+                output.set_preferred(mode);
+                x += size.w;
+
+                let mut render_loop = RenderLoop::new(loop_handle.clone(), &output, move |state| {
+                    let output = state.as_headless_mut().backend.outputs[i].output.clone();
+                    state.pre_repaint(&output);
+                    state.as_headless_mut().render(i);
+                });
+                render_loop.start();
+
+                let damage_tracker = OutputDamageTracker::from_output(&output);
+
+                HeadlessOutput {
+                    output,
+                    render_loop,
+                    damage_tracker,
+                    buffer: None,
+                    wl_output_global: None,
+                }
+            })
+            .collect();
+
+        Ok(HeadlessBackend {
+            renderer,
+            outputs,
+            dmabuf_state: smithay::wayland::dmabuf::DmabufState::new(),
+        })
+    }
+}
+
+impl smithay::wayland::buffer::BufferHandler for HeadlessBackend {
+    fn buffer_destroyed(&mut self, _buffer: &wayland_server::protocol::wl_buffer::WlBuffer) {}
+}
+
+impl crate::backend::DmabufHandlerDelegate for HeadlessBackend {
+    fn dmabuf_state(&mut self) -> &mut smithay::wayland::dmabuf::DmabufState {
+        &mut self.dmabuf_state
+    }
+
+    fn dmabuf_imported(
+        &mut self,
+        _global: &smithay::wayland::dmabuf::DmabufGlobal,
+        dmabuf: smithay::backend::allocator::dmabuf::Dmabuf,
+    ) -> bool {
+        self.renderer
+            .as_mut()
+            .is_some_and(|renderer| renderer.import_dmabuf(&dmabuf, None).is_ok())
+    }
+}
+
+impl BackendI for HeadlessBackend {
+    fn init(&mut self, inner: &mut InnerState) -> eyre::Result<()> {
+        for o in &mut self.outputs {
+            o.wl_output_global = Some(WlGlobal::new(
+                o.output
+                    .create_global::<SabiniwmState>(&inner.display_handle),
+                inner.display_handle.clone(),
+            ));
+            inner
+                .space
+                .map_output(&o.output, o.output.current_location());
+            inner.on_output_added(&o.output);
+        }
+
+        if let Some(renderer) = &self.renderer {
+            inner.shm_state.update_formats(renderer.shm_formats());
+        }
+
+        Ok(())
+    }
+
+    fn has_relative_motion(&self) -> bool {
+        false
+    }
+
+    fn has_gesture(&self) -> bool {
+        false
+    }
+
+    fn has_screen_capture(&self) -> bool {
+        false
+    }
+
+    fn seat_name(&self) -> String {
+        String::from("headless")
+    }
+
+    fn early_import(&mut self, _surface: &wl_surface::WlSurface) {}
+
+    fn update_led_state(&mut self, _led_state: smithay::input::keyboard::LedState) {}
+
+    fn change_vt(&mut self, _vt: i32) {
+        error!("changing VT is not supported on headless backend");
+    }
+}
+
+impl SabiniwmState {
+    fn as_headless_mut(&mut self) -> SabiniwmStateWithConcreteBackend<'_, HeadlessBackend> {
+        SabiniwmStateWithConcreteBackend {
+            backend: self.backend.as_headless_mut(),
+            inner: &mut self.inner,
+        }
+    }
+}
+
+fn render_output(
+    renderer: &mut PixmanRenderer,
+    o: &mut HeadlessOutput,
+    elements: &[OutputRenderElement<PixmanRenderer, WindowRenderElement<PixmanRenderer>>],
+    clear_color: Color32F,
+) -> eyre::Result<(bool, RenderElementStates)> {
+    // The buffer is reused, so its age is 1 except for the first time.
+    let age = if o.buffer.is_some() { 1 } else { 0 };
+    if o.buffer.is_none() {
+        let size = o.output.current_mode().unwrap().size;
+        let buffer = Offscreen::<pixman::Image<'static, 'static>>::create_buffer(
+            renderer,
+            Fourcc::Argb8888,
+            (size.w, size.h).into(),
+        )
+        .map_err(|e| eyre::eyre!("{}", e))?;
+        o.buffer = Some(buffer);
+    }
+    let buffer = o.buffer.as_mut().unwrap();
+    let mut fb = renderer.bind(buffer).map_err(|e| eyre::eyre!("{}", e))?;
+    let result = o
+        .damage_tracker
+        .render_output(renderer, &mut fb, age, elements, clear_color)
+        .map_err(|e| eyre::eyre!("{:?}", e))?;
+    Ok((result.damage.is_some(), result.states))
+}
+
+impl SabiniwmStateWithConcreteBackend<'_, HeadlessBackend> {
+    fn render(&mut self, index: usize) {
+        let HeadlessBackend {
+            renderer, outputs, ..
+        } = &mut *self.backend;
+        let o = &mut outputs[index];
+
+        let time = self.inner.clock.now();
+        let (has_rendered, states) = match renderer {
+            Some(renderer) => {
+                let (elements, clear_color) =
+                    self.inner.make_output_elements(renderer, &o.output, vec![]);
+                let result = render_output(renderer, o, &elements, clear_color);
+                match result {
+                    Ok(x) => x,
+                    Err(e) => {
+                        warn!("Rendering error: {:?}", e);
+                        (false, RenderElementStates::default())
+                    }
+                }
+            }
+            None => (false, RenderElementStates::default()),
+        };
+
+        // Send frame events so that clients start drawing their next frame.
+        self.inner
+            .post_repaint(&o.output, &states, None, time.into());
+
+        if has_rendered {
+            use smithay::wayland::presentation::Refresh;
+
+            let mut output_presentation_feedback =
+                self.inner.take_presentation_feedback(&o.output, &states);
+            output_presentation_feedback.presented(
+                time,
+                Refresh::fixed(Duration::from_secs_f64(1_000f64 / REFRESH_RATE as f64)),
+                0,
+                wp_presentation_feedback::Kind::empty(),
+            )
+        }
+
+        o.render_loop.on_render_frame(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output_sizes() {
+        assert_eq!(
+            parse_output_sizes("1920x1080, 1280x720").unwrap(),
+            vec![Size::from((1920, 1080)), Size::from((1280, 720))]
+        );
+        assert!(parse_output_sizes("1920").is_err());
+        assert!(parse_output_sizes("0x1080").is_err());
+        assert!(parse_output_sizes("").is_err());
+    }
+}
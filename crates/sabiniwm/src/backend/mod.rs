@@ -1,3 +1,5 @@
+#[cfg(feature = "headless")]
+pub mod headless;
 pub mod udev;
 #[cfg(feature = "winit")]
 pub mod winit;
@@ -19,6 +21,8 @@ pub(crate) trait BackendI: DmabufHandlerDelegate {
     fn init(&mut self, inner: &mut crate::state::InnerState) -> eyre::Result<()>;
     fn has_relative_motion(&self) -> bool;
     fn has_gesture(&self) -> bool;
+    /// Returns true iff the backend serves screen capture protocols, i.e. screencopy and image copy capture.
+    fn has_screen_capture(&self) -> bool;
     fn seat_name(&self) -> String;
     fn early_import(&mut self, surface: &wayland_server::protocol::wl_surface::WlSurface);
     fn update_led_state(&mut self, led_state: smithay::input::keyboard::LedState);
@@ -31,6 +35,8 @@ pub(crate) enum Backend {
     Udev(udev::UdevBackend),
     #[cfg(feature = "winit")]
     Winit(winit::WinitBackend),
+    #[cfg(feature = "headless")]
+    Headless(headless::HeadlessBackend),
 }
 
 #[thin_delegate::fill_delegate(
@@ -40,6 +46,8 @@ pub(crate) enum Backend {
             Self::Udev(backend) => f(backend),
             #[cfg(feature = "winit")]
             Self::Winit(backend) => f(backend),
+            #[cfg(feature = "headless")]
+            Self::Headless(backend) => f(backend),
         }
     }
 )]
@@ -51,6 +59,8 @@ impl smithay::wayland::buffer::BufferHandler for Backend {}
             Self::Udev(backend) => f(backend),
             #[cfg(feature = "winit")]
             Self::Winit(backend) => f(backend),
+            #[cfg(feature = "headless")]
+            Self::Headless(backend) => f(backend),
         }
     }
 )]
@@ -62,6 +72,8 @@ impl DmabufHandlerDelegate for Backend {}
             Self::Udev(backend) => f(backend),
             #[cfg(feature = "winit")]
             Self::Winit(backend) => f(backend),
+            #[cfg(feature = "headless")]
+            Self::Headless(backend) => f(backend),
         }
     }
 )]
@@ -73,6 +85,8 @@ impl Backend {
             Self::Udev(backend) => backend,
             #[cfg(feature = "winit")]
             Self::Winit(_) => unreachable!(),
+            #[cfg(feature = "headless")]
+            Self::Headless(_) => unreachable!(),
         }
     }
 
@@ -81,6 +95,8 @@ impl Backend {
             Self::Udev(backend) => backend,
             #[cfg(feature = "winit")]
             Self::Winit(_) => unreachable!(),
+            #[cfg(feature = "headless")]
+            Self::Headless(_) => unreachable!(),
         }
     }
 
@@ -89,6 +105,18 @@ impl Backend {
         match self {
             Self::Udev(_) => unreachable!(),
             Self::Winit(backend) => backend,
+            #[cfg(feature = "headless")]
+            Self::Headless(_) => unreachable!(),
+        }
+    }
+
+    #[cfg(feature = "headless")]
+    fn as_headless_mut(&mut self) -> &mut headless::HeadlessBackend {
+        match self {
+            Self::Udev(_) => unreachable!(),
+            #[cfg(feature = "winit")]
+            Self::Winit(_) => unreachable!(),
+            Self::Headless(backend) => backend,
         }
     }
 
@@ -97,6 +125,8 @@ impl Backend {
             Self::Udev(_) => true,
             #[cfg(feature = "winit")]
             Self::Winit(_) => false,
+            #[cfg(feature = "headless")]
+            Self::Headless(_) => false,
         }
     }
 }
@@ -323,6 +323,10 @@ impl BackendI for UdevBackend {
         true
     }
 
+    fn has_screen_capture(&self) -> bool {
+        true
+    }
+
     fn seat_name(&self) -> String {
         self.session.seat()
     }
@@ -780,10 +784,6 @@ impl SabiniwmStateWithConcreteBackend<'_, UdevBackend> {
                 output.set_preferred(mode.into());
                 output.change_current_state(Some(mode.into()), None, Some(scale), Some(position));
                 self.inner.space.map_output(&output, position);
-                let size = self.inner.space.output_geometry(&output)
-                    .unwrap(/* Space::map_output() and Output::change_current_state() is called. */)
-                    .size;
-                self.inner.view.resize_output(size, &mut self.inner.space);
                 self.inner.on_output_added(&output);
 
                 output.user_data().insert_if_missing(|| UdevOutputId {
@@ -914,6 +914,7 @@ impl SabiniwmStateWithConcreteBackend<'_, UdevBackend> {
                 .cloned();
 
             if let Some(output) = output {
+                self.inner.on_output_removed(&output);
                 self.inner.space.unmap_output(&output);
             }
         }
@@ -1210,9 +1211,24 @@ impl SabiniwmStateWithConcreteBackend<'_, UdevBackend> {
             &self.inner.dnd_icon,
             &mut self.inner.cursor_status,
         );
+        let cursor_element_count = additional_elements.len();
         let (elements, clear_color) =
             self.inner
                 .make_output_elements(&mut renderer, &output, additional_elements);
+        self.inner.render_screencopy(
+            &mut renderer,
+            &output,
+            &elements,
+            cursor_element_count,
+            clear_color,
+        );
+        self.inner.render_image_copy_capture(
+            &mut renderer,
+            &output,
+            &elements,
+            cursor_element_count,
+            clear_color,
+        );
         let result = self.inner.render_surface_data(
             surface,
             &mut renderer,
@@ -73,7 +73,7 @@ impl WinitBackend {
             Some((0, 0).into()),
         );
         output.set_preferred(mode);
-        // `InnerState::on_output_added()` will be called later, at the head of `init()`, as it requires `InnerState`.
+        // `InnerState::on_output_added()` will be called later, in `init()`, as it requires `InnerState`.
 
         let mut render_loop = RenderLoop::new(loop_handle.clone(), &output, move |state| {
             let output = state.as_winit_mut().backend.output.clone();
@@ -120,6 +120,7 @@ impl crate::backend::DmabufHandlerDelegate for WinitBackend {
 
 impl BackendI for WinitBackend {
     fn init(&mut self, inner: &mut InnerState) -> eyre::Result<()> {
+        inner.space.map_output(&self.output, (0, 0));
         inner.on_output_added(&self.output);
 
         #[cfg(feature = "egl")]
@@ -173,8 +174,6 @@ impl BackendI for WinitBackend {
             .shm_state
             .update_formats(self.backend.renderer().shm_formats());
 
-        inner.space.map_output(&self.output, (0, 0));
-
         Ok(())
     }
 
@@ -186,6 +185,10 @@ impl BackendI for WinitBackend {
         false
     }
 
+    fn has_screen_capture(&self) -> bool {
+        true
+    }
+
     fn seat_name(&self) -> String {
         String::from("winit")
     }
@@ -225,10 +228,7 @@ impl EventHandler<WinitEvent> for SabiniwmState {
                 output.set_preferred(mode);
                 output.change_current_state(Some(mode), None, None, None);
                 this.inner.space.map_output(output, (0, 0));
-                let size = this.inner.space.output_geometry(output)
-                    .unwrap(/* Space::map_output() and Output::change_current_state() is called. */)
-                    .size;
-                this.inner.view.resize_output(size, &mut this.inner.space);
+                this.inner.on_output_changed(output);
             }
             WinitEvent::Focus(_) | WinitEvent::Redraw => {}
         }
@@ -313,9 +313,24 @@ impl SabiniwmStateWithConcreteBackend<'_, WinitBackend> {
                 }
             }
 
+            let cursor_element_count = elements.len();
             let (elements, clear_color) =
                 self.inner
                     .make_output_elements(renderer, &self.backend.output, elements);
+            self.inner.render_screencopy(
+                renderer,
+                &self.backend.output,
+                &elements,
+                cursor_element_count,
+                clear_color,
+            );
+            self.inner.render_image_copy_capture(
+                renderer,
+                &self.backend.output,
+                &elements,
+                cursor_element_count,
+                clear_color,
+            );
             // TODO: Integrate it with the below `match`.
             match damage_tracker.render_output(renderer, &mut fb, age, &elements, clear_color) {
                 Ok(x) => Ok(x),
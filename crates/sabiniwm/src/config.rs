@@ -20,6 +20,16 @@ pub trait ConfigDelegateUnstableI {
         unstable_default::make_layout_tree_builder()
     }
 
+    /// Makes the initial layout tree of the workspace. Defaults to `make_layout_tree_builder()`.
+    ///
+    /// It is also used to reset the layout with `ActionWorkspaceResetLayout`.
+    fn make_layout_tree_builder_for_workspace(
+        &self,
+        _tag: &sabiniwm::view::stackset::WorkspaceTag,
+    ) -> sabiniwm::view::layout_node::LayoutTreeBuilder {
+        self.make_layout_tree_builder()
+    }
+
     fn make_workspace_tags(&self) -> Vec<sabiniwm::view::stackset::WorkspaceTag> {
         use sabiniwm::view::stackset::WorkspaceTag;
 
@@ -67,6 +77,16 @@ pub trait ConfigDelegateUnstableI {
         }
     }
 
+    /// If true, the border of a float window is dropped while it covers the whole screen, e.g. a fullscreen video.
+    fn smart_border_for_float_window(&self) -> bool {
+        false
+    }
+
+    /// Named scratchpads toggled by `ActionScratchpadToggle`.
+    fn make_scratchpads(&self) -> Vec<sabiniwm::view::scratchpad::Scratchpad> {
+        vec![]
+    }
+
     fn run_manage_hook(
         &self,
         _stackset: &mut sabiniwm::view::stackset::StackSet,
@@ -150,7 +170,7 @@ pub mod unstable_default {
 
         let mut nodes = HashMap::new();
 
-        let node = LayoutNode::from(LayoutTall {});
+        let node = LayoutNode::from(LayoutTall::default());
         let node_id0 = node.id();
         nodes.insert(node_id0, node);
 
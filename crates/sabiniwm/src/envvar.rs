@@ -16,6 +16,8 @@ pub(crate) struct EnvVarGeneric {
 
 #[derive(Debug, serde::Deserialize)]
 pub(crate) struct EnvVarSabiniwm {
+    /// Prevent auto detection and use designated backend.
+    pub backend: Option<BackendName>,
     /// Prevent auto detection and use designated DRM device node.
     ///
     /// Both primary node (e.g. /dev/dri/card0) and render node (e.g. /dev/dri/renderD128) are
@@ -25,6 +27,20 @@ pub(crate) struct EnvVarSabiniwm {
     pub disable_10bit: bool,
     #[serde(default = "default_bool::<true>")]
     pub enable_direct_scanout: bool,
+    /// Sizes of virtual outputs of the headless backend, e.g. "1920x1080,1280x720".
+    #[serde(default = "default_headless_outputs")]
+    pub headless_outputs: String,
+    /// If false, the headless backend doesn't render outputs.
+    #[serde(default = "default_bool::<true>")]
+    pub headless_render: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BackendName {
+    Udev,
+    Winit,
+    Headless,
 }
 
 // https://github.com/serde-rs/serde/issues/1030
@@ -33,6 +49,10 @@ const fn default_bool<const V: bool>() -> bool {
     V
 }
 
+fn default_headless_outputs() -> String {
+    "1920x1080".to_string()
+}
+
 impl EnvVar {
     pub fn load() -> eyre::Result<Self> {
         Ok(Self {
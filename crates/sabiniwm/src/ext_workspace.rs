@@ -0,0 +1,362 @@
+//! `ext-workspace-v1` protocol, for status bars and pagers.
+//!
+//! As workspaces are not tied to outputs in sabiniwm (xmonad-style), all workspaces are exposed in one workspace group
+//! that spans all outputs. The focused workspace is "active" and a workspace that has no windows, including minimized
+//! ones, and is not visible on any screen is "hidden".
+
+use crate::action::{ActionFnI, ActionWorkspaceFocus};
+use crate::state::{ClientState, InnerState, SabiniwmState};
+use crate::view::stackset::WorkspaceTag;
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols::ext::workspace::v1::server::ext_workspace_group_handle_v1::{
+    self, ExtWorkspaceGroupHandleV1,
+};
+use smithay::reexports::wayland_protocols::ext::workspace::v1::server::ext_workspace_handle_v1::{
+    self, ExtWorkspaceHandleV1,
+};
+use smithay::reexports::wayland_protocols::ext::workspace::v1::server::ext_workspace_manager_v1::{
+    self, ExtWorkspaceManagerV1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+const VERSION: u32 = 1;
+
+pub(crate) struct ExtWorkspaceState {
+    instances: Vec<ManagerInstance>,
+    // `View::generation()` at the last refresh.
+    view_generation: u64,
+}
+
+/// Objects of a bound `ext_workspace_manager_v1`.
+struct ManagerInstance {
+    manager: ExtWorkspaceManagerV1,
+    group: ExtWorkspaceGroupHandleV1,
+    outputs: Vec<Output>,
+    workspaces: Vec<(WorkspaceSnapshot, ExtWorkspaceHandleV1)>,
+    // Requested by `ext_workspace_handle_v1.activate` and applied on `ext_workspace_manager_v1.commit`.
+    pending_activation: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WorkspaceSnapshot {
+    tag: String,
+    index: usize,
+    state: ext_workspace_handle_v1::State,
+}
+
+impl ExtWorkspaceState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        display_handle.create_global::<SabiniwmState, ExtWorkspaceManagerV1, _>(VERSION, ());
+        Self {
+            instances: vec![],
+            view_generation: 0,
+        }
+    }
+
+    pub fn on_output_bound(&mut self, output: &Output, wl_output: &WlOutput) {
+        for instance in &self.instances {
+            if instance.manager.client() != wl_output.client() || !instance.outputs.contains(output)
+            {
+                continue;
+            }
+            instance.group.output_enter(wl_output);
+            instance.manager.done();
+        }
+    }
+}
+
+impl ManagerInstance {
+    /// Sends differences from the last state and `done` if something is changed.
+    fn update(
+        &mut self,
+        display_handle: &DisplayHandle,
+        snapshot: &[WorkspaceSnapshot],
+        outputs: &[Output],
+    ) {
+        use ext_workspace_handle_v1::WorkspaceCapabilities;
+
+        let Some(client) = self.manager.client() else {
+            return;
+        };
+        let mut changed = false;
+
+        for output in outputs {
+            if !self.outputs.contains(output) {
+                for wl_output in output.client_outputs(&client) {
+                    self.group.output_enter(&wl_output);
+                }
+                changed = true;
+            }
+        }
+        for output in &self.outputs {
+            if !outputs.contains(output) {
+                for wl_output in output.client_outputs(&client) {
+                    self.group.output_leave(&wl_output);
+                }
+                changed = true;
+            }
+        }
+        self.outputs = outputs.to_vec();
+
+        self.workspaces.retain(|(old, handle)| {
+            if snapshot.iter().any(|ws| ws.tag == old.tag) {
+                return true;
+            }
+            if handle.is_alive() {
+                self.group.workspace_leave(handle);
+                handle.removed();
+            }
+            changed = true;
+            false
+        });
+        for ws in snapshot {
+            match self
+                .workspaces
+                .iter_mut()
+                .find(|(old, _)| old.tag == ws.tag)
+            {
+                Some((old, handle)) => {
+                    if *old == *ws {
+                        continue;
+                    }
+                    if handle.is_alive() {
+                        if old.index != ws.index {
+                            handle.coordinates(coordinates(ws.index));
+                        }
+                        if old.state != ws.state {
+                            handle.state(ws.state);
+                        }
+                    }
+                    *old = ws.clone();
+                }
+                None => {
+                    let Ok(handle) = client
+                        .create_resource::<ExtWorkspaceHandleV1, _, SabiniwmState>(
+                            display_handle,
+                            self.manager.version(),
+                            ws.tag.clone(),
+                        )
+                    else {
+                        continue;
+                    };
+                    self.manager.workspace(&handle);
+                    handle.id(ws.tag.clone());
+                    handle.name(ws.tag.clone());
+                    handle.coordinates(coordinates(ws.index));
+                    handle.capabilities(WorkspaceCapabilities::Activate);
+                    handle.state(ws.state);
+                    self.group.workspace_enter(&handle);
+                    self.workspaces.push((ws.clone(), handle));
+                }
+            }
+            changed = true;
+        }
+
+        if changed {
+            self.manager.done();
+        }
+    }
+}
+
+fn coordinates(index: usize) -> Vec<u8> {
+    (index as u32).to_ne_bytes().to_vec()
+}
+
+impl InnerState {
+    fn make_ext_workspace_snapshot(&self) -> Vec<WorkspaceSnapshot> {
+        use ext_workspace_handle_v1::State;
+
+        let stackset = self.view.stackset();
+        stackset
+            .workspaces
+            .as_vec()
+            .iter()
+            .enumerate()
+            .map(|(i, ws)| {
+                let mut state = State::empty();
+                if i == stackset.workspaces.focused_index() {
+                    state |= State::Active;
+                }
+                if ws.stack.as_vec().iter().any(|&id| self.view.is_urgent(id)) {
+                    state |= State::Urgent;
+                }
+                if stackset.is_workspace_unused(i) {
+                    state |= State::Hidden;
+                }
+                WorkspaceSnapshot {
+                    tag: ws.tag.0.clone(),
+                    index: i,
+                    state,
+                }
+            })
+            .collect()
+    }
+
+    /// Sends changes of workspaces to clients.
+    pub fn refresh_ext_workspace(&mut self) {
+        if self.ext_workspace_state.instances.is_empty()
+            || self.ext_workspace_state.view_generation == self.view.generation()
+        {
+            return;
+        }
+        self.ext_workspace_state.view_generation = self.view.generation();
+
+        let snapshot = self.make_ext_workspace_snapshot();
+        let outputs = self.space.outputs().cloned().collect::<Vec<_>>();
+        for instance in &mut self.ext_workspace_state.instances {
+            instance.update(&self.display_handle, &snapshot, &outputs);
+        }
+    }
+}
+
+impl GlobalDispatch<ExtWorkspaceManagerV1, ()> for SabiniwmState {
+    fn bind(
+        state: &mut Self,
+        display_handle: &DisplayHandle,
+        client: &Client,
+        resource: New<ExtWorkspaceManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        use ext_workspace_group_handle_v1::GroupCapabilities;
+
+        let manager = data_init.init(resource, ());
+        let Ok(group) = client.create_resource::<ExtWorkspaceGroupHandleV1, _, Self>(
+            display_handle,
+            manager.version(),
+            (),
+        ) else {
+            return;
+        };
+        manager.workspace_group(&group);
+        group.capabilities(GroupCapabilities::empty());
+
+        let mut instance = ManagerInstance {
+            manager,
+            group,
+            outputs: vec![],
+            workspaces: vec![],
+            pending_activation: None,
+        };
+        let snapshot = state.inner.make_ext_workspace_snapshot();
+        let outputs = state.inner.space.outputs().cloned().collect::<Vec<_>>();
+        instance.update(display_handle, &snapshot, &outputs);
+        state.inner.ext_workspace_state.instances.push(instance);
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        client
+            .get_data::<ClientState>()
+            .is_none_or(|client_state| client_state.security_context.is_none())
+    }
+}
+
+impl Dispatch<ExtWorkspaceManagerV1, ()> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtWorkspaceManagerV1,
+        request: ext_workspace_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        let instances = &mut state.inner.ext_workspace_state.instances;
+        match request {
+            ext_workspace_manager_v1::Request::Commit => {
+                let Some(instance) = instances.iter_mut().find(|x| x.manager == *resource) else {
+                    return;
+                };
+                let Some(tag) = instance.pending_activation.take() else {
+                    return;
+                };
+                let tag = WorkspaceTag(tag);
+                // The workspace may have been removed after the client requested.
+                let exists = state
+                    .inner
+                    .view
+                    .stackset()
+                    .workspaces
+                    .as_vec()
+                    .iter()
+                    .any(|ws| ws.tag == tag);
+                if exists {
+                    state.process_action(&ActionWorkspaceFocus::WithTag(tag).into_action());
+                }
+            }
+            ext_workspace_manager_v1::Request::Stop => {
+                resource.finished();
+                instances.retain(|x| x.manager != *resource);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ExtWorkspaceManagerV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .ext_workspace_state
+            .instances
+            .retain(|x| x.manager != *resource);
+    }
+}
+
+impl Dispatch<ExtWorkspaceGroupHandleV1, ()> for SabiniwmState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtWorkspaceGroupHandleV1,
+        request: ext_workspace_group_handle_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            // Not advertised in capabilities.
+            ext_workspace_group_handle_v1::Request::CreateWorkspace { .. } => {}
+            ext_workspace_group_handle_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceHandleV1, String> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtWorkspaceHandleV1,
+        request: ext_workspace_handle_v1::Request,
+        tag: &String,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_workspace_handle_v1::Request::Activate => {
+                let instance = state
+                    .inner
+                    .ext_workspace_state
+                    .instances
+                    .iter_mut()
+                    .find(|x| x.workspaces.iter().any(|(_, handle)| handle == resource));
+                if let Some(instance) = instance {
+                    instance.pending_activation = Some(tag.clone());
+                }
+            }
+            // Not advertised in capabilities.
+            ext_workspace_handle_v1::Request::Deactivate
+            | ext_workspace_handle_v1::Request::Assign { .. }
+            | ext_workspace_handle_v1::Request::Remove => {}
+            ext_workspace_handle_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
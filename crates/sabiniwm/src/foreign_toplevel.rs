@@ -0,0 +1,472 @@
+//! Foreign toplevel protocols, for taskbars and window switchers.
+//!
+//! - `ext-foreign-toplevel-list-v1`: Listing only. Provided by smithay.
+//! - `wlr-foreign-toplevel-management-unstable-v1`: Listing and control. Implemented here as smithay doesn't provide
+//!   it.
+//!
+//! sabiniwm doesn't have fullscreen state. A float window covering an output is reported as fullscreen, and
+//! `set_fullscreen`/`unset_fullscreen` make a window such a float window/sink it to the stack.
+//!
+//! Minimized windows are kept in the list. `activate` restores a minimized window.
+
+use crate::action::{ActionFnI, ActionWindowSink};
+use crate::state::{ClientState, InnerState, SabiniwmState};
+use crate::util::Id;
+use crate::view::window::{Window, WindowQuery};
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1;
+use smithay::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_handle_v1::{
+    self, ZwlrForeignToplevelHandleV1,
+};
+use smithay::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_manager_v1::{
+    self, ZwlrForeignToplevelManagerV1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use smithay::wayland::foreign_toplevel_list::{
+    ForeignToplevelHandle, ForeignToplevelListHandler, ForeignToplevelListState,
+};
+use std::collections::HashMap;
+
+const WLR_VERSION: u32 = 3;
+
+pub(crate) struct ForeignToplevelState {
+    list_state: ForeignToplevelListState,
+    wlr_managers: Vec<ZwlrForeignToplevelManagerV1>,
+    toplevels: HashMap<Id<Window>, ToplevelEntry>,
+    // `View::generation()` at the last refresh.
+    view_generation: u64,
+}
+
+struct ToplevelEntry {
+    snapshot: ToplevelSnapshot,
+    ext_handle: ForeignToplevelHandle,
+    wlr_handles: Vec<ZwlrForeignToplevelHandleV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ToplevelSnapshot {
+    title: String,
+    app_id: String,
+    activated: bool,
+    fullscreen: bool,
+    minimized: bool,
+    outputs: Vec<Output>,
+}
+
+fn can_view(client: &Client) -> bool {
+    client
+        .get_data::<ClientState>()
+        .is_none_or(|client_state| client_state.security_context.is_none())
+}
+
+impl ForeignToplevelState {
+    pub fn new(display_handle: &DisplayHandle) -> Self {
+        let list_state =
+            ForeignToplevelListState::new_with_filter::<SabiniwmState>(display_handle, can_view);
+        display_handle
+            .create_global::<SabiniwmState, ZwlrForeignToplevelManagerV1, _>(WLR_VERSION, ());
+        Self {
+            list_state,
+            wlr_managers: vec![],
+            toplevels: HashMap::new(),
+            view_generation: 0,
+        }
+    }
+
+    pub fn on_output_bound(&mut self, output: &Output, wl_output: &WlOutput) {
+        for entry in self.toplevels.values() {
+            if !entry.snapshot.outputs.contains(output) {
+                continue;
+            }
+            for handle in &entry.wlr_handles {
+                if handle.client() == wl_output.client() {
+                    handle.output_enter(wl_output);
+                    handle.done();
+                }
+            }
+        }
+    }
+
+    /// Returns the window of an `ext_foreign_toplevel_handle_v1`.
+    pub fn window_id_of(&self, resource: &ExtForeignToplevelHandleV1) -> Option<Id<Window>> {
+        let handle = ForeignToplevelHandle::from_resource(resource)?;
+        self.toplevels
+            .iter()
+            .find(|(_, entry)| entry.ext_handle.identifier() == handle.identifier())
+            .map(|(&id, _)| id)
+    }
+}
+
+impl ToplevelEntry {
+    fn send_diff(&self, new: &ToplevelSnapshot) {
+        let old = &self.snapshot;
+
+        if old.title != new.title {
+            self.ext_handle.send_title(&new.title);
+        }
+        if old.app_id != new.app_id {
+            self.ext_handle.send_app_id(&new.app_id);
+        }
+        if old.title != new.title || old.app_id != new.app_id {
+            self.ext_handle.send_done();
+        }
+
+        for handle in &self.wlr_handles {
+            if old.title != new.title {
+                handle.title(new.title.clone());
+            }
+            if old.app_id != new.app_id {
+                handle.app_id(new.app_id.clone());
+            }
+            if let Some(client) = handle.client() {
+                for output in &new.outputs {
+                    if !old.outputs.contains(output) {
+                        for wl_output in output.client_outputs(&client) {
+                            handle.output_enter(&wl_output);
+                        }
+                    }
+                }
+                for output in &old.outputs {
+                    if !new.outputs.contains(output) {
+                        for wl_output in output.client_outputs(&client) {
+                            handle.output_leave(&wl_output);
+                        }
+                    }
+                }
+            }
+            if old.activated != new.activated
+                || old.fullscreen != new.fullscreen
+                || old.minimized != new.minimized
+            {
+                handle.state(wlr_state(handle, new));
+            }
+            handle.done();
+        }
+    }
+}
+
+fn wlr_state(handle: &ZwlrForeignToplevelHandleV1, snapshot: &ToplevelSnapshot) -> Vec<u8> {
+    use zwlr_foreign_toplevel_handle_v1::State;
+
+    let mut states = vec![];
+    if snapshot.activated {
+        states.push(State::Activated);
+    }
+    if snapshot.fullscreen && handle.version() >= 2 {
+        states.push(State::Fullscreen);
+    }
+    if snapshot.minimized {
+        states.push(State::Minimized);
+    }
+    states
+        .into_iter()
+        .flat_map(|state| (state as u32).to_ne_bytes())
+        .collect()
+}
+
+fn init_wlr_handle(
+    display_handle: &DisplayHandle,
+    manager: &ZwlrForeignToplevelManagerV1,
+    window_id: Id<Window>,
+    snapshot: &ToplevelSnapshot,
+) -> Option<ZwlrForeignToplevelHandleV1> {
+    let client = manager.client()?;
+    let handle = client
+        .create_resource::<ZwlrForeignToplevelHandleV1, _, SabiniwmState>(
+            display_handle,
+            manager.version(),
+            window_id,
+        )
+        .ok()?;
+    manager.toplevel(&handle);
+    handle.title(snapshot.title.clone());
+    handle.app_id(snapshot.app_id.clone());
+    for output in &snapshot.outputs {
+        for wl_output in output.client_outputs(&client) {
+            handle.output_enter(&wl_output);
+        }
+    }
+    handle.state(wlr_state(&handle, snapshot));
+    handle.done();
+    Some(handle)
+}
+
+impl InnerState {
+    fn make_toplevel_snapshot(&self, window: &Window) -> ToplevelSnapshot {
+        let wq = WindowQuery::new(
+            window.clone(),
+            self.display_handle.clone(),
+            self.view.focused_screen_rect(),
+        );
+        let stackset = self.view.stackset();
+        let fullscreen = stackset
+            .float_windows
+            .iter()
+            .find(|fw| fw.id == window.id())
+            .is_some_and(|fw| {
+                self.space
+                    .outputs()
+                    .any(|output| self.space.output_geometry(output) == Some(fw.geometry))
+            });
+        ToplevelSnapshot {
+            title: wq.title().unwrap_or_default(),
+            app_id: wq.app_id().or_else(|| wq.x_class()).unwrap_or_default(),
+            activated: self.view.focused_window().map(|w| w.id()) == Some(window.id()),
+            fullscreen,
+            minimized: stackset.is_window_minimized(window.id()),
+            outputs: self.space.outputs_for_element(window),
+        }
+    }
+
+    /// Sends changes of windows to clients.
+    pub fn refresh_foreign_toplevel(&mut self) {
+        if self.foreign_toplevel_state.view_generation == self.view.generation() {
+            return;
+        }
+        self.foreign_toplevel_state.view_generation = self.view.generation();
+
+        let snapshots = self
+            .view
+            .windows()
+            .map(|window| (window.id(), self.make_toplevel_snapshot(window)))
+            .collect::<HashMap<_, _>>();
+
+        let state = &mut self.foreign_toplevel_state;
+        state.toplevels.retain(|id, entry| {
+            if snapshots.contains_key(id) {
+                return true;
+            }
+            state.list_state.remove_toplevel(&entry.ext_handle);
+            for handle in &entry.wlr_handles {
+                handle.closed();
+            }
+            false
+        });
+        for (id, snapshot) in snapshots {
+            match state.toplevels.get_mut(&id) {
+                Some(entry) => {
+                    if entry.snapshot != snapshot {
+                        entry.send_diff(&snapshot);
+                        entry.snapshot = snapshot;
+                    }
+                }
+                None => {
+                    let ext_handle = state
+                        .list_state
+                        .new_toplevel::<SabiniwmState>(&snapshot.title, &snapshot.app_id);
+                    let wlr_handles = state
+                        .wlr_managers
+                        .iter()
+                        .filter_map(|manager| {
+                            init_wlr_handle(&self.display_handle, manager, id, &snapshot)
+                        })
+                        .collect();
+                    state.toplevels.insert(
+                        id,
+                        ToplevelEntry {
+                            snapshot,
+                            ext_handle,
+                            wlr_handles,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl SabiniwmState {
+    fn handle_foreign_toplevel_request(
+        &mut self,
+        window_id: Id<Window>,
+        request: zwlr_foreign_toplevel_handle_v1::Request,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::Request;
+
+        // Same as `SabiniwmState::process_action()`.
+        #[cfg(not(feature = "debug_session_lock_client_dead"))]
+        if self.inner.session_lock_data.is_locked() {
+            return;
+        }
+
+        let Some(window) = self.inner.view.get_window(window_id) else {
+            return;
+        };
+
+        match request {
+            Request::Activate { .. } => {
+                self.inner.view.update_stackset_with(|stackset| {
+                    stackset.restore_window(window_id);
+                });
+                self.inner.view.set_focus(window_id);
+            }
+            Request::Close => {
+                window.close();
+                return;
+            }
+            Request::SetFullscreen { output } => {
+                let output = output
+                    .as_ref()
+                    .and_then(Output::from_resource)
+                    .or_else(|| {
+                        self.inner
+                            .space
+                            .outputs_for_element(window)
+                            .into_iter()
+                            .next()
+                    })
+                    .or_else(|| {
+                        let screen = self.inner.view.stackset().screens().focus()?;
+                        Some(screen.output.clone())
+                    });
+                let Some(rect) = output.and_then(|o| self.inner.space.output_geometry(&o)) else {
+                    return;
+                };
+                self.inner.view.update_stackset_with(|stackset| {
+                    stackset.float_window_with_rect(window_id, rect);
+                });
+                self.inner
+                    .view
+                    .update_float_window_with(window_id, |fw| fw.geometry = rect);
+            }
+            Request::UnsetFullscreen => {
+                let is_float = self
+                    .inner
+                    .view
+                    .stackset()
+                    .float_windows
+                    .iter()
+                    .any(|fw| fw.id == window_id);
+                if !is_float {
+                    return;
+                }
+                self.inner.view.set_focus(window_id);
+                // Lays out and reflects focus.
+                self.process_action(&ActionWindowSink {}.into_action());
+                return;
+            }
+            Request::SetMinimized => {
+                self.inner
+                    .view
+                    .update_stackset_with(|stackset| stackset.minimize_window(window_id));
+            }
+            Request::UnsetMinimized => {
+                self.inner
+                    .view
+                    .update_stackset_with(|stackset| stackset.restore_window(window_id));
+            }
+            // Not supported.
+            Request::SetMaximized | Request::UnsetMaximized | Request::SetRectangle { .. } => {
+                return
+            }
+            Request::Destroy => return,
+            _ => unreachable!(),
+        }
+
+        self.inner.view.layout(&mut self.inner.space);
+        self.reflect_focus_from_stackset();
+    }
+}
+
+impl ForeignToplevelListHandler for SabiniwmState {
+    fn foreign_toplevel_list_state(&mut self) -> &mut ForeignToplevelListState {
+        &mut self.inner.foreign_toplevel_state.list_state
+    }
+}
+
+smithay::delegate_foreign_toplevel_list!(SabiniwmState);
+
+impl GlobalDispatch<ZwlrForeignToplevelManagerV1, ()> for SabiniwmState {
+    fn bind(
+        state: &mut Self,
+        display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrForeignToplevelManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+        let state = &mut state.inner.foreign_toplevel_state;
+        for (&id, entry) in &mut state.toplevels {
+            if let Some(handle) = init_wlr_handle(display_handle, &manager, id, &entry.snapshot) {
+                entry.wlr_handles.push(handle);
+            }
+        }
+        state.wlr_managers.push(manager);
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrForeignToplevelManagerV1,
+        request: zwlr_foreign_toplevel_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_foreign_toplevel_manager_v1::Request::Stop => {
+                resource.finished();
+                state
+                    .inner
+                    .foreign_toplevel_state
+                    .wlr_managers
+                    .retain(|x| x != resource);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrForeignToplevelManagerV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .foreign_toplevel_state
+            .wlr_managers
+            .retain(|x| x != resource);
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, Id<Window>> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ZwlrForeignToplevelHandleV1,
+        request: zwlr_foreign_toplevel_handle_v1::Request,
+        window_id: &Id<Window>,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        state.handle_foreign_toplevel_request(*window_id, request);
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrForeignToplevelHandleV1,
+        window_id: &Id<Window>,
+    ) {
+        if let Some(entry) = state
+            .inner
+            .foreign_toplevel_state
+            .toplevels
+            .get_mut(window_id)
+        {
+            entry.wlr_handles.retain(|x| x != resource);
+        }
+    }
+}
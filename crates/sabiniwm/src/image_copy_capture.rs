@@ -0,0 +1,672 @@
+//! `ext-image-capture-source-v1` and `ext-image-copy-capture-v1` protocols, for screencast, e.g. through
+//! xdg-desktop-portal.
+//!
+//! Sources are outputs and toplevels. A toplevel source is created from an `ext_foreign_toplevel_handle_v1` and is
+//! keyed by `Id<Window>`. A captured frame is rendered when the backend renders an output that shows the source, and
+//! only if the source is damaged since the last frame of the session.
+//!
+//! Only shm buffers are advertised. Cursor sessions are not supported and their capture sessions are stopped
+//! immediately.
+
+use crate::screencopy::{is_buffer_valid, render_to_buffer, Capture, SHM_FORMAT};
+use crate::state::{ClientState, InnerState, SabiniwmState};
+use crate::util::Id;
+use crate::view::window::Window;
+use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::renderer::damage::OutputDamageTracker;
+use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
+use smithay::backend::renderer::element::{AsRenderElements, RenderElement};
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::backend::renderer::{
+    Bind, Color32F, ExportMem, ImportAll, ImportMem, Offscreen, Renderer, Texture,
+};
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::{
+    self, ExtForeignToplevelImageCaptureSourceManagerV1,
+};
+use smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_image_capture_source_v1::{
+    self, ExtImageCaptureSourceV1,
+};
+use smithay::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::{
+    self, ExtOutputImageCaptureSourceManagerV1,
+};
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_cursor_session_v1::{
+    self, ExtImageCopyCaptureCursorSessionV1,
+};
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_frame_v1::{
+    self, ExtImageCopyCaptureFrameV1,
+};
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::{
+    self, ExtImageCopyCaptureManagerV1,
+};
+use smithay::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_session_v1::{
+    self, ExtImageCopyCaptureSessionV1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use smithay::utils::{Logical, Physical, Point, Rectangle, Scale, Size, Transform};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const VERSION: u32 = 1;
+
+#[derive(Default)]
+pub(crate) struct ImageCopyCaptureState {
+    sessions: Vec<Session>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ImageCaptureSource {
+    Output(Output),
+    Toplevel(Id<Window>),
+    /// The output or the toplevel was already gone on creation.
+    Invalid,
+}
+
+struct Session {
+    session: ExtImageCopyCaptureSessionV1,
+    source: ImageCaptureSource,
+    paint_cursors: bool,
+    /// Buffer size last sent to the client.
+    buffer_size: Size<i32, Physical>,
+    /// Tracks damage between frames. Reset when the buffer size is changed.
+    damage_tracker: Option<OutputDamageTracker>,
+    /// At most one frame exists at a time.
+    frame: Option<ExtImageCopyCaptureFrameV1>,
+    /// The frame and the buffer waiting for damage after `capture` is requested.
+    pending: Option<(ExtImageCopyCaptureFrameV1, WlBuffer)>,
+}
+
+pub(crate) struct FrameData {
+    session: ExtImageCopyCaptureSessionV1,
+    state: Mutex<FrameState>,
+}
+
+#[derive(Default)]
+struct FrameState {
+    buffer: Option<WlBuffer>,
+    captured: bool,
+}
+
+impl ImageCopyCaptureState {
+    pub fn create_globals(display_handle: &DisplayHandle) {
+        display_handle
+            .create_global::<SabiniwmState, ExtOutputImageCaptureSourceManagerV1, _>(VERSION, ());
+        display_handle
+            .create_global::<SabiniwmState, ExtForeignToplevelImageCaptureSourceManagerV1, _>(
+                VERSION,
+                (),
+            );
+        display_handle.create_global::<SabiniwmState, ExtImageCopyCaptureManagerV1, _>(VERSION, ());
+    }
+
+    fn session_mut(&mut self, session: &ExtImageCopyCaptureSessionV1) -> Option<&mut Session> {
+        self.sessions.iter_mut().find(|x| x.session == *session)
+    }
+}
+
+impl Session {
+    /// Sends buffer constraints. `size` is `None` if the source is not available.
+    fn send_constraints(&mut self, size: Option<Size<i32, Physical>>) {
+        let Some(size) = size else {
+            self.stop();
+            return;
+        };
+        self.buffer_size = size;
+        self.damage_tracker = None;
+        self.session.buffer_size(size.w as u32, size.h as u32);
+        self.session.shm_format(SHM_FORMAT);
+        self.session.done();
+    }
+
+    fn stop(&mut self) {
+        use ext_image_copy_capture_frame_v1::FailureReason;
+
+        if let Some((frame, _)) = self.pending.take() {
+            frame.failed(FailureReason::Stopped);
+        }
+        self.session.stopped();
+        self.source = ImageCaptureSource::Invalid;
+    }
+
+    /// Renders the pending frame if the source is damaged since the last frame.
+    #[allow(clippy::too_many_arguments)]
+    fn render_frame<R, E>(
+        &mut self,
+        renderer: &mut R,
+        size: Size<i32, Physical>,
+        scale: Scale<f64>,
+        transform: Transform,
+        elements: &[E],
+        clear_color: Color32F,
+        time: Duration,
+    ) where
+        R: Renderer + ExportMem + Offscreen<GlesTexture> + Bind<GlesTexture> + Bind<Dmabuf>,
+        E: RenderElement<R>,
+    {
+        use ext_image_copy_capture_frame_v1::FailureReason;
+
+        let Some((frame, buffer)) = self.pending.take() else {
+            return;
+        };
+        if !frame.is_alive() {
+            return;
+        }
+        if size != self.buffer_size {
+            frame.failed(FailureReason::BufferConstraints);
+            self.send_constraints(Some(size));
+            return;
+        }
+
+        let damage_tracker = self
+            .damage_tracker
+            .get_or_insert_with(|| OutputDamageTracker::new(size, scale, transform));
+        let damage = match damage_tracker.damage_output(1, elements) {
+            Ok((damage, _)) => damage.cloned().unwrap_or_default(),
+            Err(_) => vec![Rectangle::from_size(size)],
+        };
+        if damage.is_empty() {
+            // Wait for the next render.
+            self.pending = Some((frame, buffer));
+            return;
+        }
+
+        let mut damage_tracker = OutputDamageTracker::new(size, scale, transform);
+        match render_to_buffer(
+            renderer,
+            &mut damage_tracker,
+            size,
+            elements,
+            clear_color,
+            &buffer,
+        ) {
+            Ok(()) => {
+                frame.transform(transform.into());
+                for rect in damage {
+                    frame.damage(rect.loc.x, rect.loc.y, rect.size.w, rect.size.h);
+                }
+                let secs = time.as_secs();
+                frame.presentation_time((secs >> 32) as u32, secs as u32, time.subsec_nanos());
+                frame.ready();
+            }
+            Err(e) => {
+                warn!("image copy capture failed: {:?}", e);
+                frame.failed(FailureReason::Unknown);
+            }
+        }
+    }
+}
+
+impl InnerState {
+    /// Returns the buffer size of the source, or `None` if it is not available.
+    fn image_capture_source_size(
+        &self,
+        source: &ImageCaptureSource,
+    ) -> Option<Size<i32, Physical>> {
+        match source {
+            ImageCaptureSource::Output(output) => {
+                Some(Capture::new(output.clone(), None, false)?.buffer_size())
+            }
+            ImageCaptureSource::Toplevel(window_id) => {
+                let window = self.space.elements().find(|w| w.id() == *window_id)?;
+                let scale = self
+                    .space
+                    .outputs_for_element(window)
+                    .first()
+                    .map(|output| output.current_scale().fractional_scale())
+                    .unwrap_or(1.0);
+                let size = window.smithay_window().geometry().size;
+                Some(size.to_f64().to_physical(scale).to_i32_round())
+            }
+            ImageCaptureSource::Invalid => None,
+        }
+    }
+
+    /// Serves pending frames whose sources are shown on the output. Backends call it with elements made by
+    /// `make_output_elements()`.
+    pub(crate) fn render_image_copy_capture<R, E>(
+        &mut self,
+        renderer: &mut R,
+        output: &Output,
+        elements: &[E],
+        cursor_element_count: usize,
+        clear_color: Color32F,
+    ) where
+        R: Renderer + ImportAll + ImportMem,
+        R: ExportMem + Offscreen<GlesTexture> + Bind<GlesTexture> + Bind<Dmabuf>,
+        R::TextureId: Texture + Clone + 'static,
+        E: RenderElement<R>,
+    {
+        let time = Duration::from(self.clock.now());
+        let output_scale = Scale::from(output.current_scale().fractional_scale());
+        for session in &mut self.image_copy_capture_state.sessions {
+            if session.pending.is_none() {
+                continue;
+            }
+            match &session.source {
+                ImageCaptureSource::Output(source) => {
+                    if source != output {
+                        continue;
+                    }
+                    let Some(capture) = Capture::new(output.clone(), None, session.paint_cursors)
+                    else {
+                        continue;
+                    };
+                    let elements = capture.relocate_elements(elements, cursor_element_count);
+                    session.render_frame(
+                        renderer,
+                        capture.buffer_size(),
+                        output_scale,
+                        output.current_transform(),
+                        &elements,
+                        clear_color,
+                        time,
+                    );
+                }
+                ImageCaptureSource::Toplevel(window_id) => {
+                    let Some(window) = self.space.elements().find(|w| w.id() == *window_id) else {
+                        continue;
+                    };
+                    if self.space.outputs_for_element(window).first() != Some(output) {
+                        continue;
+                    }
+                    // Render without decorations, with the window geometry at the origin.
+                    let swindow = window.smithay_window();
+                    let geometry = swindow.geometry();
+                    let loc = Point::<i32, Logical>::from((-geometry.loc.x, -geometry.loc.y))
+                        .to_f64()
+                        .to_physical(output_scale)
+                        .to_i32_round();
+                    let elements: Vec<WaylandSurfaceRenderElement<R>> =
+                        swindow.render_elements(renderer, loc, output_scale, 1.0);
+                    let size = geometry
+                        .size
+                        .to_f64()
+                        .to_physical(output_scale)
+                        .to_i32_round();
+                    session.render_frame(
+                        renderer,
+                        size,
+                        output_scale,
+                        Transform::Normal,
+                        &elements,
+                        clear_color,
+                        time,
+                    );
+                }
+                ImageCaptureSource::Invalid => {}
+            }
+        }
+    }
+
+    /// Stops sessions whose sources are gone and forgets destroyed ones.
+    pub fn refresh_image_copy_capture(&mut self) {
+        let mut sessions = std::mem::take(&mut self.image_copy_capture_state.sessions);
+        sessions.retain_mut(|session| {
+            if !session.session.is_alive() {
+                return false;
+            }
+            let is_available = match &session.source {
+                ImageCaptureSource::Output(output) => self.space.outputs().any(|o| o == output),
+                ImageCaptureSource::Toplevel(window_id) => {
+                    self.view.get_window(*window_id).is_some()
+                }
+                // Already stopped.
+                ImageCaptureSource::Invalid => return true,
+            };
+            if !is_available {
+                session.stop();
+            }
+            true
+        });
+        self.image_copy_capture_state.sessions = sessions;
+    }
+}
+
+fn can_view(client: &Client) -> bool {
+    client
+        .get_data::<ClientState>()
+        .is_none_or(|client_state| client_state.security_context.is_none())
+}
+
+impl GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ()> for SabiniwmState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtOutputImageCaptureSourceManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ExtOutputImageCaptureSourceManagerV1, ()> for SabiniwmState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtOutputImageCaptureSourceManagerV1,
+        request: ext_output_image_capture_source_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_output_image_capture_source_manager_v1::Request::CreateSource {
+                source,
+                output,
+            } => {
+                let source_data = match Output::from_resource(&output) {
+                    Some(output) => ImageCaptureSource::Output(output),
+                    None => ImageCaptureSource::Invalid,
+                };
+                data_init.init(source, source_data);
+            }
+            ext_output_image_capture_source_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()> for SabiniwmState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtForeignToplevelImageCaptureSourceManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ExtForeignToplevelImageCaptureSourceManagerV1,
+        request: ext_foreign_toplevel_image_capture_source_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::CreateSource {
+                source,
+                toplevel_handle,
+            } => {
+                let source_data = match state
+                    .inner
+                    .foreign_toplevel_state
+                    .window_id_of(&toplevel_handle)
+                {
+                    Some(window_id) => ImageCaptureSource::Toplevel(window_id),
+                    None => ImageCaptureSource::Invalid,
+                };
+                data_init.init(source, source_data);
+            }
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<ExtImageCaptureSourceV1, ImageCaptureSource> for SabiniwmState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtImageCaptureSourceV1,
+        request: ext_image_capture_source_v1::Request,
+        _data: &ImageCaptureSource,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_image_capture_source_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl GlobalDispatch<ExtImageCopyCaptureManagerV1, ()> for SabiniwmState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtImageCopyCaptureManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        can_view(&client)
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureManagerV1, ()> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtImageCopyCaptureManagerV1,
+        request: ext_image_copy_capture_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        use ext_image_copy_capture_manager_v1::{Error, Options};
+
+        match request {
+            ext_image_copy_capture_manager_v1::Request::CreateSession {
+                session,
+                source,
+                options,
+            } => {
+                let Ok(options) = options.into_result() else {
+                    resource.post_error(Error::InvalidOption, "invalid options");
+                    return;
+                };
+                let source = source
+                    .data::<ImageCaptureSource>()
+                    .cloned()
+                    .unwrap_or(ImageCaptureSource::Invalid);
+                let session = data_init.init(session, ());
+                let size = state.inner.image_capture_source_size(&source);
+                let mut session = Session {
+                    session,
+                    source,
+                    paint_cursors: options.contains(Options::PaintCursors),
+                    buffer_size: Size::default(),
+                    damage_tracker: None,
+                    frame: None,
+                    pending: None,
+                };
+                session.send_constraints(size);
+                state.inner.image_copy_capture_state.sessions.push(session);
+            }
+            ext_image_copy_capture_manager_v1::Request::CreatePointerCursorSession {
+                session,
+                ..
+            } => {
+                data_init.init(session, ());
+            }
+            ext_image_copy_capture_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureCursorSessionV1, ()> for SabiniwmState {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ExtImageCopyCaptureCursorSessionV1,
+        request: ext_image_copy_capture_cursor_session_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_image_copy_capture_cursor_session_v1::Request::GetCaptureSession { session } => {
+                // Not supported.
+                let session = data_init.init(session, ());
+                session.stopped();
+            }
+            ext_image_copy_capture_cursor_session_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureSessionV1, ()> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtImageCopyCaptureSessionV1,
+        request: ext_image_copy_capture_session_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        use ext_image_copy_capture_session_v1::Error;
+
+        match request {
+            ext_image_copy_capture_session_v1::Request::CreateFrame { frame } => {
+                let frame = data_init.init(
+                    frame,
+                    FrameData {
+                        session: resource.clone(),
+                        state: Mutex::new(FrameState::default()),
+                    },
+                );
+                // Sessions of cursor sessions are not tracked.
+                let Some(session) = state.inner.image_copy_capture_state.session_mut(resource)
+                else {
+                    frame.failed(ext_image_copy_capture_frame_v1::FailureReason::Stopped);
+                    return;
+                };
+                if session.frame.as_ref().is_some_and(|f| f.is_alive()) {
+                    resource.post_error(Error::DuplicateFrame, "a frame already exists");
+                    return;
+                }
+                session.frame = Some(frame);
+            }
+            ext_image_copy_capture_session_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ExtImageCopyCaptureSessionV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .image_copy_capture_state
+            .sessions
+            .retain(|x| x.session != *resource);
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureFrameV1, FrameData> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtImageCopyCaptureFrameV1,
+        request: ext_image_copy_capture_frame_v1::Request,
+        data: &FrameData,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        use ext_image_copy_capture_frame_v1::{Error, FailureReason};
+
+        let mut frame_state = data.state.lock().unwrap();
+        match request {
+            ext_image_copy_capture_frame_v1::Request::AttachBuffer { buffer } => {
+                frame_state.buffer = Some(buffer);
+            }
+            ext_image_copy_capture_frame_v1::Request::DamageBuffer {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                if x < 0 || y < 0 || width <= 0 || height <= 0 {
+                    resource.post_error(Error::InvalidBufferDamage, "invalid buffer damage");
+                }
+                // Frames are always fully rendered.
+            }
+            ext_image_copy_capture_frame_v1::Request::Capture => {
+                if frame_state.captured {
+                    resource.post_error(Error::AlreadyCaptured, "already captured");
+                    return;
+                }
+                let Some(buffer) = frame_state.buffer.clone() else {
+                    resource.post_error(Error::NoBuffer, "no buffer attached");
+                    return;
+                };
+                frame_state.captured = true;
+
+                let Some(session) = state
+                    .inner
+                    .image_copy_capture_state
+                    .session_mut(&data.session)
+                else {
+                    resource.failed(FailureReason::Stopped);
+                    return;
+                };
+                if session.source == ImageCaptureSource::Invalid {
+                    resource.failed(FailureReason::Stopped);
+                    return;
+                }
+                if !is_buffer_valid(&buffer, session.buffer_size) {
+                    resource.failed(FailureReason::BufferConstraints);
+                    return;
+                }
+                session.pending = Some((resource.clone(), buffer));
+            }
+            ext_image_copy_capture_frame_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ExtImageCopyCaptureFrameV1,
+        data: &FrameData,
+    ) {
+        if let Some(session) = state
+            .inner
+            .image_copy_capture_state
+            .session_mut(&data.session)
+        {
+            if session.frame.as_ref() == Some(resource) {
+                session.frame = None;
+            }
+            if session
+                .pending
+                .as_ref()
+                .is_some_and(|(frame, _)| frame == resource)
+            {
+                session.pending = None;
+            }
+        }
+    }
+}
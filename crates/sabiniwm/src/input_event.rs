@@ -26,6 +26,9 @@ impl SabiniwmState {
         );
         if should_update_focus {
             self.update_focus(serial);
+            if matches!(event, InputEvent::PointerButton { .. }) {
+                self.update_focus_by_decoration(serial);
+            }
         }
 
         match &event {
@@ -102,9 +105,10 @@ impl SabiniwmState {
                     pointer.current_location()
                 );
 
-                let output = self.inner.space.outputs().next().unwrap();
-                let output_rect = self.inner.space.output_geometry(output).unwrap().to_f64();
-                let loc = (pointer.current_location() + event.delta()).constrain(output_rect);
+                let loc = self.clamp_pointer_location(
+                    pointer.current_location(),
+                    pointer.current_location() + event.delta(),
+                );
                 let under = self.surface_under(loc);
 
                 pointer.motion(
@@ -366,6 +370,39 @@ impl SabiniwmState {
         }
     }
 
+    /// Clamps a pointer location so that the pointer stays on outputs.
+    ///
+    /// The pointer can move freely across outputs. If `new` is not on any output, it is constrained to the output that
+    /// contains `current` (or the first output).
+    fn clamp_pointer_location(
+        &self,
+        current: Point<f64, Logical>,
+        new: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let space = &self.inner.space;
+        let output_rects = space
+            .outputs()
+            .map(|o| space.output_geometry(o).unwrap().to_f64())
+            .collect::<Vec<_>>();
+
+        if output_rects.iter().any(|rect| rect.contains(new)) {
+            return new;
+        }
+
+        let Some(rect) = output_rects
+            .iter()
+            .find(|rect| rect.contains(current))
+            .or_else(|| output_rects.first())
+        else {
+            return new;
+        };
+        // `Point::constrain()` allows the right/bottom edge, which is not contained in the rect.
+        let mut loc = new.constrain(*rect);
+        loc.x = loc.x.min(rect.loc.x + rect.size.w - 1.0);
+        loc.y = loc.y.min(rect.loc.y + rect.size.h - 1.0);
+        loc
+    }
+
     fn reset_focus_if_session_is_locked(
         &mut self,
         serial: Serial,
@@ -431,6 +468,29 @@ impl SabiniwmState {
         self.reflect_focus_from_stackset_aux(serial);
     }
 
+    /// Clicking a decoration, e.g. a tab, focuses the associated window.
+    fn update_focus_by_decoration(&mut self, serial: Serial) {
+        let pointer = self.inner.seat.get_pointer().unwrap();
+        let pos = pointer.current_location();
+
+        if self.inner.session_lock_data.is_locked() || self.inner.space.element_under(pos).is_some()
+        {
+            return;
+        }
+        let Some(window_id) = self
+            .inner
+            .view
+            .decoration_under(pos)
+            .and_then(|decoration| decoration.focus_on_click)
+        else {
+            return;
+        };
+
+        self.inner.view.set_focus(window_id);
+        self.inner.view.layout(&mut self.inner.space);
+        self.reflect_focus_from_stackset_aux(serial);
+    }
+
     pub(crate) fn reflect_focus_from_stackset(&mut self) {
         let serial = SERIAL_COUNTER.next_serial();
         let pointer = self.inner.seat.get_pointer().unwrap();
@@ -447,9 +507,13 @@ impl SabiniwmState {
     pub(crate) fn reflect_focus_from_stackset_aux(&mut self, serial: Serial) {
         use smithay::desktop::space::SpaceElement;
 
-        let Some(window) = self.inner.view.focused_window() else {
+        self.inner.emit_ipc_focus_events();
+
+        let Some(window_id) = self.inner.view.focused_window().map(|w| w.id()) else {
             return;
         };
+        self.inner.view.set_urgent(window_id, false);
+        let window = self.inner.view.get_window(window_id).unwrap();
 
         for w in self.inner.space.elements() {
             w.set_activate(false);
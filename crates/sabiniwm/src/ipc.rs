@@ -0,0 +1,631 @@
+use crate::action::{self, Action, ActionFnI};
+use crate::state::SabiniwmState;
+use crate::util::Id;
+use crate::view::predefined::{
+    LayoutMessageIncMaster, LayoutMessageReset, LayoutMessageResize, LayoutMessageSelect,
+    LayoutMessageToggle,
+};
+use crate::view::stackset::{WindowFocusType, WorkspaceError, WorkspaceTag};
+use crate::view::window::{Window, WindowQuery};
+use sabiniwm_ipc as proto;
+use smithay::reexports::calloop::generic::Generic;
+use smithay::reexports::calloop::{Interest, LoopHandle, Mode, PostAction};
+use smithay::utils::{Logical, Rectangle};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Maximum length of a pending request. A client that sends a longer line is disconnected.
+const MAX_REQUEST_LEN: usize = 64 * 1024;
+
+/// Listens on `$XDG_RUNTIME_DIR/sabiniwm.<WAYLAND_DISPLAY>.<pid>.sock` and serves `sabiniwm_ipc::Request`s.
+///
+/// The socket file is removed on drop.
+pub(crate) struct IpcServer {
+    socket_path: PathBuf,
+    subscribers: Vec<UnixStream>,
+    snapshot: EventSnapshot,
+}
+
+/// Part of the state that was last sent to subscribers. It is not maintained while no subscriber exists.
+#[derive(Default)]
+struct EventSnapshot {
+    focused_workspace: String,
+    focused_window: Option<Id<Window>>,
+    window_titles: HashMap<Id<Window>, Option<String>>,
+    // `View::generation()` when `window_titles` was last checked.
+    view_generation: u64,
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+impl IpcServer {
+    pub fn new(
+        loop_handle: &LoopHandle<'static, SabiniwmState>,
+        wayland_socket_name: &str,
+    ) -> eyre::Result<Self> {
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .ok_or_else(|| eyre::eyre!("XDG_RUNTIME_DIR is not set"))?;
+        let socket_path = runtime_dir.join(format!(
+            "sabiniwm.{}.{}.sock",
+            wayland_socket_name,
+            std::process::id()
+        ));
+        // Remove a stale socket, which is left if sabiniwm crashed with the same pid.
+        let _ = std::fs::remove_file(&socket_path);
+
+        let listener = UnixListener::bind(&socket_path)?;
+        listener.set_nonblocking(true)?;
+        loop_handle
+            .insert_source(
+                Generic::new(listener, Interest::READ, Mode::Level),
+                |_, listener, state| {
+                    loop {
+                        match listener.as_ref().accept() {
+                            Ok((stream, _)) => state.inner.on_ipc_client_connected(stream),
+                            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                            Err(e) => {
+                                warn!("Error accepting IPC client: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                    Ok(PostAction::Continue)
+                },
+            )
+            .map_err(|e| eyre::eyre!("{}", e))?;
+
+        std::env::set_var(proto::SOCKET_ENV, &socket_path);
+        info!(
+            "Start listening on IPC socket: {} = {}",
+            proto::SOCKET_ENV,
+            socket_path.display()
+        );
+
+        Ok(Self {
+            socket_path,
+            subscribers: vec![],
+            snapshot: EventSnapshot::default(),
+        })
+    }
+
+    fn broadcast(&mut self, events: &[proto::Event]) {
+        if events.is_empty() {
+            return;
+        }
+
+        debug!("IPC events: {:?}", events);
+        self.subscribers.retain(|stream| {
+            for event in events {
+                if let Err(e) = write_line(stream, event) {
+                    debug!("IPC subscriber disconnected: {}", e);
+                    return false;
+                }
+            }
+            true
+        });
+    }
+
+    /// Sends `WindowMapped` unless it has already been sent for the window.
+    fn send_window_mapped(&mut self, window: proto::Window) {
+        let id = Id::from(window.id);
+        if self.snapshot.window_titles.contains_key(&id) {
+            return;
+        }
+        self.snapshot.window_titles.insert(id, window.title.clone());
+        self.broadcast(&[proto::Event::WindowMapped { window }]);
+    }
+}
+
+fn write_line(mut stream: &UnixStream, x: &impl serde::Serialize) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(x).unwrap(/* Serialization never fails. */);
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+impl crate::state::InnerState {
+    fn on_ipc_client_connected(&mut self, stream: UnixStream) {
+        // Avoid the compositor being blocked by a client that doesn't read responses/events.
+        if let Err(e) = stream.set_write_timeout(Some(Duration::from_millis(100))) {
+            warn!("Error setting up IPC client: {}", e);
+            return;
+        }
+
+        let mut buf = Vec::new();
+        let mut subscribed = false;
+        let ret = self.loop_handle.insert_source(
+            Generic::new(stream, Interest::READ, Mode::Level),
+            move |_, stream, state| {
+                let stream = stream.as_ref();
+                let mut chunk = [0; 4096];
+                let n = match (&*stream).read(&mut chunk) {
+                    Ok(0) => return Ok(PostAction::Remove),
+                    Ok(n) => n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                        return Ok(PostAction::Continue)
+                    }
+                    Err(e) => {
+                        debug!("IPC client disconnected: {}", e);
+                        return Ok(PostAction::Remove);
+                    }
+                };
+                // The connection is dedicated to the subscription. Keep reading only to detect disconnection.
+                if subscribed {
+                    return Ok(PostAction::Continue);
+                }
+                buf.extend_from_slice(&chunk[..n]);
+
+                while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                    let line = buf.drain(..=pos).collect::<Vec<_>>();
+                    let response = match serde_json::from_slice::<proto::Request>(&line) {
+                        Ok(proto::Request::Subscribe) => {
+                            subscribed = true;
+                            proto::Response::Ok
+                        }
+                        Ok(request) => state.handle_ipc_request(request),
+                        Err(e) => proto::Response::Error {
+                            message: format!("invalid request: {}", e),
+                        },
+                    };
+                    if let Err(e) = write_line(stream, &response) {
+                        debug!("IPC client disconnected: {}", e);
+                        return Ok(PostAction::Remove);
+                    }
+                    if subscribed {
+                        match stream.try_clone() {
+                            Ok(stream) => state.inner.add_ipc_subscriber(stream),
+                            Err(e) => {
+                                warn!("Error adding IPC subscriber: {}", e);
+                                return Ok(PostAction::Remove);
+                            }
+                        }
+                        buf.clear();
+                        break;
+                    }
+                }
+                if buf.len() > MAX_REQUEST_LEN {
+                    warn!(
+                        "IPC request exceeds {} bytes. Disconnecting the client.",
+                        MAX_REQUEST_LEN
+                    );
+                    return Ok(PostAction::Remove);
+                }
+                Ok(PostAction::Continue)
+            },
+        );
+        if let Err(e) = ret {
+            warn!("Error adding IPC client: {}", e);
+        }
+    }
+
+    fn add_ipc_subscriber(&mut self, stream: UnixStream) {
+        // Events are calculated as differences from the last snapshot, which is not maintained while no subscriber
+        // exists.
+        let Some(subscribers) = self.ipc_server.as_ref().map(|s| s.subscribers.len()) else {
+            return;
+        };
+        let snapshot = (subscribers == 0).then(|| self.make_event_snapshot());
+        let ipc_server = self.ipc_server.as_mut().unwrap(/* Checked above. */);
+        if let Some(snapshot) = snapshot {
+            ipc_server.snapshot = snapshot;
+        }
+        ipc_server.subscribers.push(stream);
+    }
+
+    fn has_ipc_subscriber(&self) -> bool {
+        self.ipc_server
+            .as_ref()
+            .is_some_and(|ipc_server| !ipc_server.subscribers.is_empty())
+    }
+
+    /// Sends events to subscribers.
+    fn broadcast_ipc_events(&mut self, events: &[proto::Event]) {
+        if let Some(ipc_server) = &mut self.ipc_server {
+            ipc_server.broadcast(events);
+        }
+    }
+
+    /// Sends an event to subscribers.
+    pub fn emit_ipc_event(&mut self, event: proto::Event) {
+        self.broadcast_ipc_events(&[event]);
+    }
+
+    /// Sends `WindowMapped`. Call this when a window is registered to the view.
+    pub fn emit_ipc_window_mapped(&mut self, id: Id<Window>) {
+        if !self.has_ipc_subscriber() {
+            return;
+        }
+
+        let window = self.dump_window(id);
+        let ipc_server = self.ipc_server.as_mut().unwrap(/* Checked above. */);
+        ipc_server.send_window_mapped(window);
+    }
+
+    /// Sends `WindowUnmapped`. Call this when a window is removed from the view.
+    pub fn emit_ipc_window_unmapped(&mut self, id: Id<Window>) {
+        let Some(ipc_server) = &mut self.ipc_server else {
+            return;
+        };
+        if ipc_server.subscribers.is_empty() {
+            return;
+        }
+
+        ipc_server.snapshot.window_titles.remove(&id);
+        ipc_server.broadcast(&[proto::Event::WindowUnmapped { id: id.as_u64() }]);
+    }
+
+    /// Sends `WorkspaceFocusChanged` and `WindowFocusChanged` if the focus differs from the one last sent. Call this
+    /// when the focus of the stackset is reflected.
+    pub fn emit_ipc_focus_events(&mut self) {
+        if !self.has_ipc_subscriber() {
+            return;
+        }
+
+        let focused_workspace = self.view.stackset().workspaces.focus().tag.0.clone();
+        let focused_window = self.view.focused_window().map(|window| window.id());
+        let snapshot = &mut self.ipc_server.as_mut().unwrap(/* Checked above. */).snapshot;
+
+        let mut events = vec![];
+        if snapshot.focused_workspace != focused_workspace {
+            snapshot.focused_workspace = focused_workspace.clone();
+            events.push(proto::Event::WorkspaceFocusChanged {
+                tag: focused_workspace,
+            });
+        }
+        if snapshot.focused_window != focused_window {
+            snapshot.focused_window = focused_window;
+            events.push(proto::Event::WindowFocusChanged {
+                id: focused_window.map(|id| id.as_u64()),
+            });
+        }
+
+        self.broadcast_ipc_events(&events);
+    }
+
+    /// Sends `WindowTitleChanged` for windows whose titles differ from the ones last sent.
+    ///
+    /// Titles are checked only if the view has been changed since the last call. Title changes mark it changed.
+    pub fn emit_ipc_title_events(&mut self) {
+        let view_generation = self.view.generation();
+        if self.ipc_server.as_ref().is_none_or(|ipc_server| {
+            ipc_server.subscribers.is_empty()
+                || ipc_server.snapshot.view_generation == view_generation
+        }) {
+            return;
+        }
+
+        let window_titles = self.make_event_snapshot().window_titles;
+        let snapshot = &mut self.ipc_server.as_mut().unwrap(/* Checked above. */).snapshot;
+        snapshot.view_generation = view_generation;
+
+        let mut events = vec![];
+        for (id, title) in window_titles {
+            // Windows that are not sent by `WindowMapped` yet are handled by `emit_ipc_window_mapped()`.
+            let Some(old_title) = snapshot.window_titles.get_mut(&id) else {
+                continue;
+            };
+            if *old_title != title {
+                *old_title = title.clone();
+                events.push(proto::Event::WindowTitleChanged {
+                    id: id.as_u64(),
+                    title,
+                });
+            }
+        }
+
+        self.broadcast_ipc_events(&events);
+    }
+
+    fn make_event_snapshot(&self) -> EventSnapshot {
+        let focused_screen_rect = self.view.focused_screen_rect();
+        let window_titles = self
+            .view
+            .windows()
+            .map(|window| {
+                let wq = WindowQuery::new(
+                    window.clone(),
+                    self.display_handle.clone(),
+                    focused_screen_rect,
+                );
+                (window.id(), wq.title())
+            })
+            .collect();
+
+        EventSnapshot {
+            focused_workspace: self.view.stackset().workspaces.focus().tag.0.clone(),
+            focused_window: self.view.focused_window().map(|window| window.id()),
+            window_titles,
+            view_generation: self.view.generation(),
+        }
+    }
+}
+
+impl SabiniwmState {
+    fn handle_ipc_request(&mut self, request: proto::Request) -> proto::Response {
+        debug!("IPC request: {:?}", request);
+
+        match request {
+            proto::Request::GetStackSet => proto::Response::StackSet {
+                stackset: self.inner.dump_stackset(),
+            },
+            proto::Request::Dispatch { action } => {
+                #[cfg(not(feature = "debug_session_lock_client_dead"))]
+                if self.inner.session_lock_data.is_locked() {
+                    return proto::Response::Error {
+                        message: "actions are not allowed if session is locked".to_string(),
+                    };
+                }
+
+                match self.inner.convert_ipc_action(action) {
+                    Ok(action) => {
+                        self.process_action(&action);
+                        proto::Response::Ok
+                    }
+                    Err(message) => proto::Response::Error { message },
+                }
+            }
+            // The connection handler takes over `Subscribe` as it needs the connection. Reaching here is a bug, but
+            // it's not worth aborting the compositor.
+            proto::Request::Subscribe => proto::Response::Error {
+                message: "subscribe is not supported in this context".to_string(),
+            },
+        }
+    }
+}
+
+impl crate::state::InnerState {
+    fn convert_ipc_action(&self, action: proto::Action) -> Result<Action, String> {
+        use proto::{Direction, WorkspaceTarget};
+
+        let stackset = self.view.stackset();
+        let workspace_tag = |tag: String| {
+            let tag = WorkspaceTag(tag);
+            match stackset.workspace_index(&tag) {
+                Ok(_) => Ok(tag),
+                Err(e) => Err(e.to_string()),
+            }
+        };
+        // Workspace management, restoring windows and scratchpads are checked beforehand as errors of actions are not
+        // reported to the caller.
+        let check = |result: Result<(), WorkspaceError>| result.map_err(|e| e.to_string());
+
+        let action = match action {
+            proto::Action::Spawn { command } => Action::Spawn(command),
+            proto::Action::WorkspaceFocus { target } => match target {
+                WorkspaceTarget::Next => action::ActionWorkspaceFocus::Next,
+                WorkspaceTarget::Prev => action::ActionWorkspaceFocus::Prev,
+                WorkspaceTarget::Tag(tag) => {
+                    action::ActionWorkspaceFocus::WithTag(workspace_tag(tag)?)
+                }
+            }
+            .into_action(),
+            proto::Action::WorkspaceGreedyFocus { target } => match target {
+                WorkspaceTarget::Next => action::ActionWorkspaceGreedyFocus::Next,
+                WorkspaceTarget::Prev => action::ActionWorkspaceGreedyFocus::Prev,
+                WorkspaceTarget::Tag(tag) => {
+                    action::ActionWorkspaceGreedyFocus::WithTag(workspace_tag(tag)?)
+                }
+            }
+            .into_action(),
+            proto::Action::WindowMoveToWorkspace { target } => match target {
+                WorkspaceTarget::Next => action::ActionWindowMoveToWorkspace::Next,
+                WorkspaceTarget::Prev => action::ActionWindowMoveToWorkspace::Prev,
+                WorkspaceTarget::Tag(tag) => {
+                    action::ActionWindowMoveToWorkspace::WithTag(workspace_tag(tag)?)
+                }
+            }
+            .into_action(),
+            proto::Action::ScreenFocus { direction } => match direction {
+                Direction::Next => action::ActionScreenFocus::Next,
+                Direction::Prev => action::ActionScreenFocus::Prev,
+            }
+            .into_action(),
+            proto::Action::ScreenSwap { direction } => match direction {
+                Direction::Next => action::ActionScreenSwap::Next,
+                Direction::Prev => action::ActionScreenSwap::Prev,
+            }
+            .into_action(),
+            proto::Action::WindowMoveToScreen { direction } => match direction {
+                Direction::Next => action::ActionWindowMoveToScreen::Next,
+                Direction::Prev => action::ActionWindowMoveToScreen::Prev,
+            }
+            .into_action(),
+            proto::Action::MoveFocus { direction } => match direction {
+                Direction::Next => action::ActionMoveFocus::Next,
+                Direction::Prev => action::ActionMoveFocus::Prev,
+            }
+            .into_action(),
+            proto::Action::WindowSwap { direction } => match direction {
+                Direction::Next => action::ActionWindowSwap::Next,
+                Direction::Prev => action::ActionWindowSwap::Prev,
+            }
+            .into_action(),
+            proto::Action::WindowKill => action::ActionWindowKill {}.into_action(),
+            proto::Action::WindowFloat => action::ActionWindowFloat {}.into_action(),
+            proto::Action::WindowSink => action::ActionWindowSink {}.into_action(),
+            proto::Action::WindowMinimize => action::ActionWindowMinimize {}.into_action(),
+            proto::Action::WindowRestore { id } => match id {
+                Some(id) => {
+                    if !stackset.is_window_minimized(Id::from(id)) {
+                        return Err(format!("window {id} is not minimized"));
+                    }
+                    action::ActionWindowRestore::Window(Id::from(id))
+                }
+                None => action::ActionWindowRestore::Last,
+            }
+            .into_action(),
+            proto::Action::WorkspaceResetLayout => {
+                action::ActionWorkspaceResetLayout {}.into_action()
+            }
+            proto::Action::WorkspaceAdd { tag } => {
+                let tag = WorkspaceTag(tag);
+                check(stackset.check_add_workspace(&tag))?;
+                action::ActionWorkspaceAdd(tag).into_action()
+            }
+            proto::Action::WorkspaceRename { tag, new_tag } => {
+                let tag = workspace_tag(tag)?;
+                let new_tag = WorkspaceTag(new_tag);
+                check(stackset.check_rename_workspace(&tag, &new_tag))?;
+                action::ActionWorkspaceRename { tag, new_tag }.into_action()
+            }
+            proto::Action::WorkspaceDelete {
+                tag,
+                move_windows_to,
+            } => {
+                let tag = workspace_tag(tag)?;
+                let move_windows_to = move_windows_to.map(workspace_tag).transpose()?;
+                check(stackset.check_delete_workspace(&tag, move_windows_to.as_ref()))?;
+                action::ActionWorkspaceDelete {
+                    tag,
+                    move_windows_to,
+                }
+                .into_action()
+            }
+            proto::Action::WorkspaceMove { tag, index } => action::ActionWorkspaceMove {
+                tag: workspace_tag(tag)?,
+                index,
+            }
+            .into_action(),
+            proto::Action::ScratchpadToggle { name } => {
+                if !self.view.has_scratchpad(&name) {
+                    return Err(format!("no scratchpad named {name:?}"));
+                }
+                action::ActionScratchpadToggle(name).into_action()
+            }
+            proto::Action::LayoutMessage { message } => match message {
+                proto::LayoutMessage::SelectNext => LayoutMessageSelect::Next.into(),
+                proto::LayoutMessage::SelectPrev => LayoutMessageSelect::Prev.into(),
+                proto::LayoutMessage::Toggle => LayoutMessageToggle.into(),
+                proto::LayoutMessage::IncMaster { delta } => LayoutMessageIncMaster(delta).into(),
+                proto::LayoutMessage::Shrink => LayoutMessageResize::Shrink.into(),
+                proto::LayoutMessage::Expand => LayoutMessageResize::Expand.into(),
+                proto::LayoutMessage::Reset => LayoutMessageReset.into(),
+            },
+        };
+        Ok(action)
+    }
+
+    fn dump_stackset(&self) -> proto::StackSet {
+        let stackset = self.view.stackset();
+        let workspaces = stackset
+            .workspaces
+            .as_vec()
+            .iter()
+            .enumerate()
+            .map(|(i, ws)| proto::Workspace {
+                tag: ws.tag.0.clone(),
+                focused: i == stackset.workspaces.focused_index(),
+                visible: stackset.is_workspace_visible(i),
+                stack: ws
+                    .stack
+                    .as_vec()
+                    .iter()
+                    .map(|&id| self.dump_window(id))
+                    .collect(),
+                stack_focus: ws.stack.focus().map(|id| id.as_u64()),
+                layout: ws.layout_description(),
+                minimized: ws
+                    .minimized
+                    .iter()
+                    .map(|mw| self.dump_window(mw.id))
+                    .collect(),
+            })
+            .collect();
+        let screens = stackset
+            .screens
+            .as_vec()
+            .iter()
+            .enumerate()
+            .map(|(i, screen)| proto::Screen {
+                output: screen.output.name(),
+                rect: dump_rect(screen.rect),
+                workspace: stackset.workspaces.as_vec()[screen.workspace_index]
+                    .tag
+                    .0
+                    .clone(),
+                focused: i == stackset.screens.focused_index(),
+            })
+            .collect();
+        let float_windows = stackset
+            .float_windows
+            .iter()
+            .map(|fw| proto::FloatWindow {
+                window: self.dump_window(fw.id),
+                geometry: dump_rect(fw.geometry),
+            })
+            .collect();
+        let focused_window = match stackset.window_focus_type {
+            WindowFocusType::Stack => stackset.workspaces.focus().stack.focus().copied(),
+            WindowFocusType::Float => stackset.float_windows.last().map(|fw| fw.id),
+        };
+
+        proto::StackSet {
+            workspaces,
+            screens,
+            float_windows,
+            focused_window: focused_window.map(|id| id.as_u64()),
+        }
+    }
+
+    fn dump_window(&self, id: Id<Window>) -> proto::Window {
+        let window = self.view.get_window(id).unwrap(/* Windows in the stackset are registered. */);
+        let wq = WindowQuery::new(
+            window.clone(),
+            self.display_handle.clone(),
+            self.view.focused_screen_rect(),
+        );
+        proto::Window {
+            id: id.as_u64(),
+            app_id: wq.app_id(),
+            title: wq.title(),
+        }
+    }
+}
+
+fn dump_rect(rect: Rectangle<i32, Logical>) -> proto::Rect {
+    proto::Rect {
+        x: rect.loc.x,
+        y: rect.loc.y,
+        w: rect.size.w,
+        h: rect.size.h,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn test_window_mapped_is_sent_once() {
+        let (server_stream, client_stream) = UnixStream::pair().unwrap();
+        let mut ipc_server = IpcServer {
+            socket_path: PathBuf::new(),
+            subscribers: vec![server_stream],
+            snapshot: EventSnapshot::default(),
+        };
+        let window = proto::Window {
+            id: 1,
+            app_id: Some("foot".to_string()),
+            title: Some("foot".to_string()),
+        };
+
+        ipc_server.send_window_mapped(window.clone());
+        ipc_server.send_window_mapped(window.clone());
+        // Close the subscriber so that the client reads until EOF.
+        drop(ipc_server);
+
+        let events = BufReader::new(client_stream)
+            .lines()
+            .map(|line| serde_json::from_str::<proto::Event>(&line.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![proto::Event::WindowMapped { window }]);
+    }
+}
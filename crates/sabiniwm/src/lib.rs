@@ -16,16 +16,21 @@ pub mod config;
 mod const_;
 pub mod cursor;
 mod envvar;
+mod ext_workspace;
 mod external_trait_def;
 pub mod focus;
+mod foreign_toplevel;
 mod grab;
+mod image_copy_capture;
 pub mod input;
 pub(crate) mod input_event;
 pub mod input_handler;
+mod ipc;
 pub mod model;
 pub mod pointer;
 pub mod render;
 pub(crate) mod render_loop;
+mod screencopy;
 pub(crate) mod session_lock;
 pub mod shell;
 pub(crate) mod smithay_ext;
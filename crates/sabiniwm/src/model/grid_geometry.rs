@@ -3,10 +3,18 @@ use smithay::utils::{Logical, Point, Rectangle, Size};
 use std::ops::Range;
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SplitSpec {
+    /// Fixed length.
     FixedSize(usize),
+    /// Fixed length given as a ratio to the whole length, e.g. `Ratio(0.3)`.
+    Ratio(f32),
+    /// Shares the length left by fixed ones. Same as `Weight(1)`.
     Elastic,
+    /// Shares the length left by fixed ones in proportion to the weight.
+    Weight(u32),
+    /// Same as `Weight` but the length is kept within `min..=max` if possible.
+    WeightMinMax { weight: u32, min: usize, max: usize },
 }
 
 #[allow(dead_code)]
@@ -119,71 +127,248 @@ impl RectangleExt for Rectangle<i32, Logical> {
 }
 
 fn split_range_2(specs: [SplitSpec; 2], r: &Range<i32>) -> [Range<i32>; 2] {
-    use SplitSpec::*;
-
-    let w = r.end - r.start;
-    let mid = match specs {
-        [FixedSize(n), FixedSize(m)] => {
-            let n = n as i32;
-            let m = m as i32;
-            assert_eq!(n + m, w);
-            r.start + n
-        }
-        [FixedSize(n), Elastic] => {
-            let n = n as i32;
-            assert!(n <= w);
-            r.start + n
-        }
-        [Elastic, FixedSize(n)] => {
-            let n = n as i32;
-            assert!(n <= w);
-            r.end - n
-        }
-        [Elastic, Elastic] => r.start + w / 2,
-    };
-    [r.start..mid, mid..r.end]
+    let [r0, r1] = split_range(&specs, r).try_into().unwrap();
+    [r0, r1]
 }
 
+/// Splits `r` according to `specs`.
+///
+/// This never panics even if specs can't be satisfied:
+///
+/// - If fixed lengths exceed the whole length, they are scaled down and elastic ones get zero.
+/// - If minimum lengths exceed the remaining length, they are scaled down.
+/// - If there is no room for the remaining length (no elastic ones or all of them hit maximum), the tail of `r` is
+///   left uncovered.
 fn split_range(specs: &[SplitSpec], r: &Range<i32>) -> Vec<Range<i32>> {
+    let w = 0.max(r.end - r.start);
+    let lens = split_length(specs, w as usize);
+    let mut i = r.start;
+    let mut rs = vec![];
+    for n in lens {
+        let n = n as i32;
+        rs.push(i..i + n);
+        i += n;
+    }
+    rs
+}
+
+fn split_length(specs: &[SplitSpec], w: usize) -> Vec<usize> {
     use SplitSpec::*;
 
-    let w = r.end - r.start;
-    let fixed_size_sum: usize = specs
+    let mut lens = vec![0; specs.len()];
+
+    // Fixed ones
+    let mut fixed = vec![];
+    for (i, spec) in specs.iter().enumerate() {
+        let n = match *spec {
+            FixedSize(n) => n,
+            Ratio(ratio) => (w as f32 * ratio.clamp(0.0, 1.0)).round() as usize,
+            Elastic | Weight(_) | WeightMinMax { .. } => continue,
+        };
+        fixed.push((i, n));
+    }
+    let fixed_sum = fixed.iter().map(|(_, n)| n).sum::<usize>();
+    if fixed_sum > w {
+        for (i, n) in distribute(w, &fixed) {
+            lens[i] = n;
+        }
+        return lens;
+    }
+    for &(i, n) in &fixed {
+        lens[i] = n;
+    }
+
+    // Elastic ones
+    let elastics = specs
         .iter()
-        .map(|s| match s {
-            FixedSize(n) => *n,
-            Elastic => 0,
+        .enumerate()
+        .filter_map(|(i, spec)| match *spec {
+            FixedSize(_) | Ratio(_) => None,
+            Elastic => Some((i, 1, 0, usize::MAX)),
+            Weight(weight) => Some((i, weight, 0, usize::MAX)),
+            WeightMinMax { weight, min, max } => Some((i, weight, min, max.max(min))),
         })
-        .sum();
-    let fixed_size_sum = fixed_size_sum as i32;
-    let elastic_count = specs.iter().filter(|&&s| s == Elastic).count() as i32;
-    assert!(fixed_size_sum <= w);
-    let elastic_size_sum = w - fixed_size_sum;
-    let (elastic_size_base, mut rest) = if elastic_count == 0 {
-        assert!(elastic_size_sum == 0);
-        (0, 0)
-    } else {
-        (
-            elastic_size_sum / elastic_count,
-            elastic_size_sum % elastic_count,
-        )
-    };
-    let mut i = r.start;
-    let mut rs = vec![];
-    for spec in specs {
-        let n = match spec {
-            FixedSize(n) => *n as i32,
-            Elastic => {
-                if rest == 0 {
-                    elastic_size_base
-                } else {
-                    rest -= 1;
-                    elastic_size_base + 1
-                }
+        .collect::<Vec<_>>();
+    let rest = w - fixed_sum;
+    let min_sum = elastics.iter().map(|&(_, _, min, _)| min).sum::<usize>();
+    if min_sum >= rest {
+        let mins = elastics
+            .iter()
+            .map(|&(i, _, min, _)| (i, min))
+            .collect::<Vec<_>>();
+        for (i, n) in distribute(rest, &mins) {
+            lens[i] = n;
+        }
+        return lens;
+    }
+
+    // Freeze ones that violate constraints one kind at a time until the rest satisfy them.
+    let mut active = elastics;
+    let mut rest = rest;
+    loop {
+        let weights = active
+            .iter()
+            .map(|&(i, weight, _, _)| (i, weight as usize))
+            .collect::<Vec<_>>();
+        let shares = distribute(rest, &weights);
+        let below_min =
+            |&(k, &(_, _, min, _)): &(usize, &(usize, u32, usize, usize))| shares[k].1 < min;
+        let above_max =
+            |&(k, &(_, _, _, max)): &(usize, &(usize, u32, usize, usize))| shares[k].1 > max;
+        let frozen = if active.iter().enumerate().any(|x| below_min(&x)) {
+            active
+                .iter()
+                .enumerate()
+                .filter(below_min)
+                .map(|(k, &(_, _, min, _))| (k, min))
+                .collect::<Vec<_>>()
+        } else if active.iter().enumerate().any(|x| above_max(&x)) {
+            active
+                .iter()
+                .enumerate()
+                .filter(above_max)
+                .map(|(k, &(_, _, _, max))| (k, max))
+                .collect::<Vec<_>>()
+        } else {
+            for (i, n) in shares {
+                lens[i] = n;
             }
+            return lens;
         };
-        rs.push(i..i + n);
-        i += n;
+        for &(k, n) in frozen.iter().rev() {
+            let (i, _, _, _) = active.remove(k);
+            lens[i] = n;
+            rest -= n;
+        }
+    }
+}
+
+/// Distributes `w` in proportion to weights. Remainders are given to earlier ones.
+fn distribute(w: usize, weights: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let weight_sum = weights.iter().map(|(_, weight)| weight).sum::<usize>();
+    if weight_sum == 0 {
+        return weights.iter().map(|&(i, _)| (i, 0)).collect();
+    }
+    let mut ret = weights
+        .iter()
+        .map(|&(i, weight)| {
+            (
+                i,
+                (w as u128 * weight as u128 / weight_sum as u128) as usize,
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut rest = w - ret.iter().map(|(_, n)| n).sum::<usize>();
+    for ((_, n), (_, weight)) in ret.iter_mut().zip(weights) {
+        if rest == 0 {
+            break;
+        }
+        if *weight > 0 {
+            *n += 1;
+            rest -= 1;
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SplitSpec::*;
+
+    #[test]
+    fn test_split_length() {
+        assert_eq!(
+            split_length(&[Elastic, Elastic, Elastic], 10),
+            vec![4, 3, 3]
+        );
+        assert_eq!(split_length(&[FixedSize(3), Elastic], 10), vec![3, 7]);
+        assert_eq!(split_length(&[Ratio(0.3), Elastic], 10), vec![3, 7]);
+        assert_eq!(split_length(&[Weight(1), Weight(3)], 100), vec![25, 75]);
+        assert_eq!(
+            split_length(
+                &[
+                    WeightMinMax {
+                        weight: 1,
+                        min: 40,
+                        max: 100
+                    },
+                    Weight(3)
+                ],
+                100
+            ),
+            vec![40, 60]
+        );
+        assert_eq!(
+            split_length(
+                &[
+                    WeightMinMax {
+                        weight: 3,
+                        min: 0,
+                        max: 50
+                    },
+                    Elastic,
+                    Elastic
+                ],
+                100
+            ),
+            vec![50, 25, 25]
+        );
+    }
+
+    #[test]
+    fn test_split_length_degrade() {
+        // Fixed lengths exceed the whole length.
+        assert_eq!(
+            split_length(&[FixedSize(30), FixedSize(10), Elastic], 20),
+            vec![15, 5, 0]
+        );
+        // Minimum lengths exceed the remaining length.
+        assert_eq!(
+            split_length(
+                &[
+                    FixedSize(10),
+                    WeightMinMax {
+                        weight: 1,
+                        min: 10,
+                        max: 10
+                    },
+                    WeightMinMax {
+                        weight: 1,
+                        min: 30,
+                        max: 30
+                    }
+                ],
+                30
+            ),
+            vec![10, 5, 15]
+        );
+        // No room for the remaining length.
+        assert_eq!(split_length(&[FixedSize(3)], 10), vec![3]);
+        assert_eq!(
+            split_length(
+                &[WeightMinMax {
+                    weight: 1,
+                    min: 0,
+                    max: 4
+                }],
+                10
+            ),
+            vec![4]
+        );
+        assert_eq!(split_length(&[Weight(0), Weight(0)], 10), vec![0, 0]);
+        assert_eq!(split_length(&[], 10), vec![]);
+    }
+
+    #[test]
+    fn test_split_range() {
+        assert_eq!(
+            split_range(&[Elastic, FixedSize(3)], &(10..20)),
+            vec![10..17, 17..20]
+        );
+        assert_eq!(
+            split_range_2([FixedSize(30), Elastic], &(10..20)),
+            [10..20, 20..20]
+        );
     }
-    rs
 }
@@ -1 +1,2 @@
 pub mod grid_geometry;
+pub mod split_tree;
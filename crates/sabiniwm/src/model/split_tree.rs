@@ -0,0 +1,407 @@
+//! Tree of containers for manual tiling, like i3.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// Children are placed from left to right.
+    Horizontal,
+    /// Children are placed from top to bottom.
+    Vertical,
+    /// Only one child is shown with tabs.
+    Tabbed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl MoveDirection {
+    fn is_forward(&self) -> bool {
+        matches!(self, MoveDirection::Right | MoveDirection::Down)
+    }
+
+    /// Returns true iff moving in the direction changes the order of children of the container.
+    fn is_along(&self, kind: ContainerKind) -> bool {
+        match self {
+            MoveDirection::Left | MoveDirection::Right => kind != ContainerKind::Vertical,
+            MoveDirection::Up | MoveDirection::Down => kind == ContainerKind::Vertical,
+        }
+    }
+
+    fn container_kind(&self) -> ContainerKind {
+        match self {
+            MoveDirection::Left | MoveDirection::Right => ContainerKind::Horizontal,
+            MoveDirection::Up | MoveDirection::Down => ContainerKind::Vertical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container<T> {
+    pub kind: ContainerKind,
+    pub children: Vec<SplitTreeNode<T>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitTreeNode<T> {
+    Leaf(T),
+    Container(Container<T>),
+}
+
+impl<T: Copy + Eq> SplitTreeNode<T> {
+    pub fn contains(&self, x: T) -> bool {
+        match self {
+            SplitTreeNode::Leaf(y) => *y == x,
+            SplitTreeNode::Container(c) => c.children.iter().any(|child| child.contains(x)),
+        }
+    }
+
+    /// Returns the first leaf in the node.
+    pub fn first_leaf(&self) -> Option<T> {
+        match self {
+            SplitTreeNode::Leaf(x) => Some(*x),
+            SplitTreeNode::Container(c) => c.children.iter().find_map(|child| child.first_leaf()),
+        }
+    }
+}
+
+/// Tree of containers whose leaves are distinct `T`s.
+///
+/// The root container is never removed. Other containers are removed when they become empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitTree<T> {
+    root: Container<T>,
+}
+
+impl<T: Copy + Eq> SplitTree<T> {
+    pub fn new(kind: ContainerKind) -> Self {
+        Self {
+            root: Container {
+                kind,
+                children: vec![],
+            },
+        }
+    }
+
+    pub fn root(&self) -> &Container<T> {
+        &self.root
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        self.root.children.iter().any(|child| child.contains(x))
+    }
+
+    /// Returns leaves in depth-first order.
+    pub fn leaves(&self) -> Vec<T> {
+        fn go<T: Copy>(c: &Container<T>, ret: &mut Vec<T>) {
+            for child in &c.children {
+                match child {
+                    SplitTreeNode::Leaf(x) => ret.push(*x),
+                    SplitTreeNode::Container(c) => go(c, ret),
+                }
+            }
+        }
+
+        let mut ret = vec![];
+        go(&self.root, &mut ret);
+        ret
+    }
+
+    /// Inserts `x` next to `target` in the same container, or at the end of the root if `target` is not in the tree.
+    pub fn insert_after(&mut self, target: Option<T>, x: T) {
+        debug_assert!(!self.contains(x));
+
+        match target.and_then(|target| self.find_path(target)) {
+            Some(mut path) => {
+                let i = path.pop().unwrap();
+                self.container_at_mut(&path)
+                    .children
+                    .insert(i + 1, SplitTreeNode::Leaf(x));
+            }
+            None => self.root.children.push(SplitTreeNode::Leaf(x)),
+        }
+    }
+
+    /// Removes leaves that don't satisfy `f`.
+    pub fn retain(&mut self, f: impl Fn(T) -> bool) {
+        fn go<T: Copy>(c: &mut Container<T>, f: &impl Fn(T) -> bool) {
+            c.children.retain_mut(|child| match child {
+                SplitTreeNode::Leaf(x) => f(*x),
+                SplitTreeNode::Container(c) => {
+                    go(c, f);
+                    true
+                }
+            });
+        }
+
+        go(&mut self.root, &f);
+        self.normalize();
+    }
+
+    /// Makes the container of `x` split in `kind`.
+    ///
+    /// If `x` has siblings, `x` is wrapped by a new container so that the next window is inserted into it. Otherwise,
+    /// the kind of the container is changed.
+    pub fn split(&mut self, x: T, kind: ContainerKind) {
+        let Some(mut path) = self.find_path(x) else {
+            return;
+        };
+        let i = path.pop().unwrap();
+        let container = self.container_at_mut(&path);
+        if container.children.len() == 1 {
+            container.kind = kind;
+        } else {
+            container.children[i] = SplitTreeNode::Container(Container {
+                kind,
+                children: vec![SplitTreeNode::Leaf(x)],
+            });
+        }
+    }
+
+    /// Changes the kind of the container of `x`.
+    pub fn set_kind(&mut self, x: T, kind: ContainerKind) {
+        let Some(mut path) = self.find_path(x) else {
+            return;
+        };
+        path.pop();
+        self.container_at_mut(&path).kind = kind;
+    }
+
+    /// Moves `x` in the direction, like i3's `move`.
+    ///
+    /// It swaps `x` with the sibling, enters the sibling container, or goes out to the nearest ancestor that is split
+    /// along the direction. If there is no such ancestor, the root is wrapped by a new container.
+    pub fn move_leaf(&mut self, x: T, direction: MoveDirection) {
+        let Some(path) = self.find_path(x) else {
+            return;
+        };
+        let forward = direction.is_forward();
+
+        let (parent_path, i) = path.split_at(path.len() - 1);
+        let i = i[0];
+        let parent = self.container_at_mut(parent_path);
+        if direction.is_along(parent.kind) {
+            let j = if forward {
+                i.checked_add(1)
+            } else {
+                i.checked_sub(1)
+            };
+            if let Some(j) = j.filter(|&j| j < parent.children.len()) {
+                match parent.children[j] {
+                    SplitTreeNode::Leaf(_) => parent.children.swap(i, j),
+                    SplitTreeNode::Container(_) => {
+                        let leaf = parent.children.remove(i);
+                        let j = if j > i { j - 1 } else { j };
+                        let SplitTreeNode::Container(c) = &mut parent.children[j] else {
+                            unreachable!();
+                        };
+                        if forward {
+                            c.children.insert(0, leaf);
+                        } else {
+                            c.children.push(leaf);
+                        }
+                    }
+                }
+                return;
+            }
+        }
+
+        // Go out to an ancestor. Note that removing the leaf doesn't change indices in ancestors as we normalize later.
+        for level in (0..path.len() - 1).rev() {
+            let ancestor_path = &path[..level];
+            let b = path[level];
+            if direction.is_along(self.container_at_mut(ancestor_path).kind) {
+                let leaf = self.remove_at(&path);
+                let j = if forward { b + 1 } else { b };
+                self.container_at_mut(ancestor_path)
+                    .children
+                    .insert(j, leaf);
+                self.normalize();
+                return;
+            }
+        }
+
+        // `x` is already at the edge.
+        if direction.is_along(self.root.kind) {
+            return;
+        }
+        let leaf = self.remove_at(&path);
+        let old_root = std::mem::replace(
+            &mut self.root,
+            Container {
+                kind: direction.container_kind(),
+                children: vec![],
+            },
+        );
+        self.root.children.push(SplitTreeNode::Container(old_root));
+        if forward {
+            self.root.children.push(leaf);
+        } else {
+            self.root.children.insert(0, leaf);
+        }
+        self.normalize();
+    }
+
+    /// Returns indices of children from the root to `x`.
+    fn find_path(&self, x: T) -> Option<Vec<usize>> {
+        fn go<T: Copy + Eq>(c: &Container<T>, x: T, path: &mut Vec<usize>) -> bool {
+            for (i, child) in c.children.iter().enumerate() {
+                path.push(i);
+                let found = match child {
+                    SplitTreeNode::Leaf(y) => *y == x,
+                    SplitTreeNode::Container(c) => go(c, x, path),
+                };
+                if found {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+
+        let mut path = vec![];
+        go(&self.root, x, &mut path).then_some(path)
+    }
+
+    fn container_at_mut(&mut self, path: &[usize]) -> &mut Container<T> {
+        let mut c = &mut self.root;
+        for &i in path {
+            let SplitTreeNode::Container(child) = &mut c.children[i] else {
+                panic!("path doesn't point a container");
+            };
+            c = child;
+        }
+        c
+    }
+
+    fn remove_at(&mut self, path: &[usize]) -> SplitTreeNode<T> {
+        let (parent_path, i) = path.split_at(path.len() - 1);
+        self.container_at_mut(parent_path).children.remove(i[0])
+    }
+
+    /// Removes empty containers and merges a container that only has a container.
+    fn normalize(&mut self) {
+        fn go<T>(c: &mut Container<T>) {
+            for child in &mut c.children {
+                if let SplitTreeNode::Container(cc) = child {
+                    go(cc);
+                    if cc.children.len() == 1
+                        && matches!(cc.children[0], SplitTreeNode::Container(_))
+                    {
+                        *child = cc.children.pop().unwrap();
+                    }
+                }
+            }
+            c.children.retain(|child| match child {
+                SplitTreeNode::Leaf(_) => true,
+                SplitTreeNode::Container(cc) => !cc.children.is_empty(),
+            });
+        }
+
+        go(&mut self.root);
+        if self.root.children.len() == 1 {
+            if let SplitTreeNode::Container(_) = &self.root.children[0] {
+                let SplitTreeNode::Container(c) = self.root.children.pop().unwrap() else {
+                    unreachable!();
+                };
+                self.root = c;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ContainerKind::*;
+
+    fn leaf(x: u32) -> SplitTreeNode<u32> {
+        SplitTreeNode::Leaf(x)
+    }
+
+    fn container(kind: ContainerKind, children: Vec<SplitTreeNode<u32>>) -> SplitTreeNode<u32> {
+        SplitTreeNode::Container(Container { kind, children })
+    }
+
+    fn make_tree(xs: &[u32]) -> SplitTree<u32> {
+        let mut tree = SplitTree::new(Horizontal);
+        for &x in xs {
+            tree.insert_after(tree.leaves().last().copied(), x);
+        }
+        tree
+    }
+
+    #[test]
+    fn test_insert_and_retain() {
+        let mut tree = make_tree(&[1, 2, 3]);
+        tree.insert_after(Some(1), 4);
+        assert_eq!(tree.leaves(), vec![1, 4, 2, 3]);
+        tree.insert_after(Some(100), 5);
+        assert_eq!(tree.leaves(), vec![1, 4, 2, 3, 5]);
+
+        tree.split(2, Vertical);
+        tree.insert_after(Some(2), 6);
+        assert_eq!(
+            tree.root().children[2],
+            container(Vertical, vec![leaf(2), leaf(6)])
+        );
+
+        tree.retain(|x| x != 2 && x != 6);
+        assert_eq!(tree.leaves(), vec![1, 4, 3, 5]);
+        assert!(tree
+            .root()
+            .children
+            .iter()
+            .all(|child| matches!(child, SplitTreeNode::Leaf(_))));
+    }
+
+    #[test]
+    fn test_split() {
+        let mut tree = make_tree(&[1]);
+        tree.split(1, Vertical);
+        assert_eq!(tree.root().kind, Vertical);
+        assert_eq!(tree.root().children, vec![leaf(1)]);
+
+        tree.set_kind(1, Tabbed);
+        assert_eq!(tree.root().kind, Tabbed);
+    }
+
+    #[test]
+    fn test_move_leaf() {
+        // Swap
+        let mut tree = make_tree(&[1, 2, 3]);
+        tree.move_leaf(1, MoveDirection::Right);
+        assert_eq!(tree.leaves(), vec![2, 1, 3]);
+        tree.move_leaf(2, MoveDirection::Left);
+        assert_eq!(tree, make_tree(&[2, 1, 3]));
+
+        // Enter a container
+        let mut tree = make_tree(&[1, 2, 3]);
+        tree.split(3, Vertical);
+        tree.move_leaf(1, MoveDirection::Right);
+        tree.move_leaf(1, MoveDirection::Right);
+        assert_eq!(
+            tree.root().children,
+            vec![leaf(2), container(Vertical, vec![leaf(1), leaf(3)])]
+        );
+
+        // Go out of the container
+        tree.move_leaf(1, MoveDirection::Left);
+        assert_eq!(
+            tree.root().children,
+            vec![leaf(2), leaf(1), container(Vertical, vec![leaf(3)])]
+        );
+
+        // Wrap the root
+        let mut tree = make_tree(&[1, 2]);
+        tree.move_leaf(2, MoveDirection::Down);
+        assert_eq!(tree.root().kind, Vertical);
+        assert_eq!(
+            tree.root().children,
+            vec![container(Horizontal, vec![leaf(1)]), leaf(2)]
+        );
+    }
+}
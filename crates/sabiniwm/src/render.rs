@@ -1,10 +1,11 @@
 use crate::pointer::{PointerRenderElement, CLEAR_COLOR};
 use crate::state::{InnerState, SabiniwmState};
 use crate::view::window::WindowRenderElement;
+use smithay::backend::renderer::element::memory::MemoryRenderBufferRenderElement;
 use smithay::backend::renderer::element::solid::SolidColorRenderElement;
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::{RenderElement, RenderElementStates, Wrap};
-use smithay::backend::renderer::{Color32F, ImportAll, ImportMem, Renderer};
+use smithay::backend::renderer::{Color32F, ImportAll, ImportMem, Renderer, Texture};
 use smithay::desktop::space::SpaceRenderElements;
 use smithay::output::Output;
 use smithay::wayland::dmabuf::DmabufFeedback;
@@ -18,6 +19,8 @@ where
 {
     Pointer(PointerRenderElement<R>),
     Surface(WaylandSurfaceRenderElement<R>),
+    Decoration(SolidColorRenderElement),
+    DecorationLabel(MemoryRenderBufferRenderElement<R>),
 }
 
 #[thin_delegate::fill_delegate(external_trait_def = crate::external_trait_def::smithay::backend::renderer::element)]
@@ -46,6 +49,8 @@ where
         match self {
             Self::Pointer(arg0) => f.debug_tuple("Pointer").field(arg0).finish(),
             Self::Surface(arg0) => f.debug_tuple("Surface").field(arg0).finish(),
+            Self::Decoration(arg0) => f.debug_tuple("Decoration").field(arg0).finish(),
+            Self::DecorationLabel(arg0) => f.debug_tuple("DecorationLabel").field(arg0).finish(),
         }
     }
 }
@@ -207,7 +212,7 @@ impl InnerState {
     )
     where
         R: Renderer + ImportAll + ImportMem,
-        R::TextureId: Clone + 'static,
+        R::TextureId: Texture + Clone + Send + 'static,
     {
         use smithay::backend::renderer::element::surface::render_elements_from_surface_tree;
         use smithay::backend::renderer::element::Kind;
@@ -267,6 +272,23 @@ impl InnerState {
             )
             .expect("output without mode?");
             elements.extend(space_elements.into_iter().map(OutputRenderElement::Space));
+
+            // Decorations of layouts are placed below windows.
+            if let Some(output_geometry) = self.space.output_geometry(output) {
+                let output_scale =
+                    smithay::utils::Scale::from(output.current_scale().fractional_scale());
+                for decoration in self.view.decorations() {
+                    if !decoration.decoration().rect.overlaps(output_geometry) {
+                        continue;
+                    }
+                    elements.extend(
+                        decoration
+                            .render_elements(renderer, output_geometry.loc, output_scale)
+                            .into_iter()
+                            .map(OutputRenderElement::Custom),
+                    );
+                }
+            }
         }
 
         (elements, CLEAR_COLOR)
@@ -0,0 +1,541 @@
+//! `wlr-screencopy-unstable-v1` protocol, for screenshot and screen recording tools, e.g. grim and wf-recorder.
+//!
+//! A copy request is queued and served when the backend renders the output next time. Frames are rendered from the
+//! elements made by `InnerState::make_output_elements()`, so a locked session is captured as the lock screen.
+//!
+//! Both shm and dmabuf buffers are supported. A frame is in the same orientation as the output buffer, i.e. the
+//! output transform is applied, as the protocol expects clients to handle `wl_output.geometry.transform`.
+
+use crate::state::{ClientState, InnerState, SabiniwmState};
+use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::allocator::{Buffer as _, Fourcc};
+use smithay::backend::renderer::damage::OutputDamageTracker;
+use smithay::backend::renderer::element::utils::{Relocate, RelocateRenderElement};
+use smithay::backend::renderer::element::RenderElement;
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::backend::renderer::{
+    buffer_type, Bind, BufferType, Color32F, ExportMem, Offscreen, Renderer, TextureMapping,
+};
+use smithay::output::Output;
+use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_frame_v1::{
+    self, ZwlrScreencopyFrameV1,
+};
+use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::{
+    self, ZwlrScreencopyManagerV1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
+use smithay::reexports::wayland_server::protocol::wl_shm;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+use smithay::utils::{Logical, Physical, Point, Rectangle, Scale, Size};
+use smithay::wayland::dmabuf::get_dmabuf;
+use smithay::wayland::shm::{with_buffer_contents, with_buffer_contents_mut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const VERSION: u32 = 3;
+
+pub(crate) const SHM_FORMAT: wl_shm::Format = wl_shm::Format::Xrgb8888;
+const FOURCC: Fourcc = Fourcc::Xrgb8888;
+
+#[derive(Default)]
+pub(crate) struct ScreencopyState {
+    pending_frames: Vec<PendingFrame>,
+    damage_trackers: Vec<DamageTrackerEntry>,
+}
+
+/// A frame that a client requested to copy into `buffer`.
+struct PendingFrame {
+    frame: ZwlrScreencopyFrameV1,
+    buffer: WlBuffer,
+    with_damage: bool,
+}
+
+/// Tracks damage between `copy_with_damage` requests of a manager, which a screen recorder typically uses.
+struct DamageTrackerEntry {
+    manager: ZwlrScreencopyManagerV1,
+    capture: Capture,
+    damage_tracker: OutputDamageTracker,
+}
+
+pub(crate) struct FrameData {
+    manager: ZwlrScreencopyManagerV1,
+    /// `None` if the frame has already failed on creation.
+    capture: Option<Capture>,
+    used: AtomicBool,
+}
+
+/// What to capture.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Capture {
+    pub output: Output,
+    /// In the physical coordinate of the output, the same as that of output elements.
+    pub region: Rectangle<i32, Physical>,
+    pub overlay_cursor: bool,
+}
+
+impl Capture {
+    /// Returns `None` if the output is disabled or `region` doesn't intersect with the output.
+    pub fn new(
+        output: Output,
+        region: Option<Rectangle<i32, Logical>>,
+        overlay_cursor: bool,
+    ) -> Option<Self> {
+        let mode = output.current_mode()?;
+        let output_rect =
+            Rectangle::from_size(output.current_transform().transform_size(mode.size));
+        let region = match region {
+            None => output_rect,
+            Some(region) => {
+                let scale = output.current_scale().fractional_scale();
+                region
+                    .to_f64()
+                    .to_physical(scale)
+                    .to_i32_round()
+                    .intersection(output_rect)?
+            }
+        };
+        Some(Self {
+            output,
+            region,
+            overlay_cursor,
+        })
+    }
+
+    /// Size of the buffer, in which the output transform is applied.
+    pub fn buffer_size(&self) -> Size<i32, Physical> {
+        self.output
+            .current_transform()
+            .transform_size(self.region.size)
+    }
+
+    fn make_damage_tracker(&self) -> OutputDamageTracker {
+        OutputDamageTracker::new(
+            self.buffer_size(),
+            Scale::from(self.output.current_scale().fractional_scale()),
+            self.output.current_transform(),
+        )
+    }
+
+    /// Picks output elements for the capture. `cursor_element_count` is the number of leading elements that draw the
+    /// cursor, i.e. `additional_elements` of `InnerState::make_output_elements()`.
+    pub fn relocate_elements<'a, E>(
+        &self,
+        elements: &'a [E],
+        cursor_element_count: usize,
+    ) -> Vec<RelocateRenderElement<&'a E>>
+    where
+        E: smithay::backend::renderer::element::Element,
+    {
+        let elements = if self.overlay_cursor {
+            elements
+        } else {
+            &elements[cursor_element_count.min(elements.len())..]
+        };
+        let offset = Point::<i32, Physical>::from((-self.region.loc.x, -self.region.loc.y));
+        elements
+            .iter()
+            .map(|e| RelocateRenderElement::from_element(e, offset, Relocate::Relative))
+            .collect()
+    }
+}
+
+impl ScreencopyState {
+    pub fn create_global(display_handle: &DisplayHandle) {
+        display_handle.create_global::<SabiniwmState, ZwlrScreencopyManagerV1, _>(VERSION, ());
+    }
+
+    /// Returns damage of the capture since the last call for the manager, in the buffer coordinate. Returns `None` if
+    /// the capture is not damaged.
+    fn damage<E>(
+        &mut self,
+        manager: &ZwlrScreencopyManagerV1,
+        capture: &Capture,
+        elements: &[E],
+    ) -> Option<Vec<Rectangle<i32, Physical>>>
+    where
+        E: smithay::backend::renderer::element::Element,
+    {
+        let entry = match self
+            .damage_trackers
+            .iter()
+            .position(|x| x.manager == *manager && x.capture == *capture)
+        {
+            Some(i) => &mut self.damage_trackers[i],
+            None => {
+                self.damage_trackers.push(DamageTrackerEntry {
+                    manager: manager.clone(),
+                    capture: capture.clone(),
+                    damage_tracker: capture.make_damage_tracker(),
+                });
+                self.damage_trackers.last_mut().unwrap()
+            }
+        };
+        let damage = match entry.damage_tracker.damage_output(1, elements) {
+            Ok((damage, _)) => damage.filter(|damage| !damage.is_empty())?.clone(),
+            Err(_) => return Some(vec![Rectangle::from_size(capture.buffer_size())]),
+        };
+        // Damage is in the coordinate of elements, and the buffer has the output transform applied.
+        let transform = capture.output.current_transform();
+        Some(
+            damage
+                .into_iter()
+                .map(|rect| transform.transform_rect_in(rect, &capture.region.size))
+                .collect(),
+        )
+    }
+}
+
+/// Checks that the buffer can hold a frame of the given size.
+pub(crate) fn is_buffer_valid(buffer: &WlBuffer, size: Size<i32, Physical>) -> bool {
+    match buffer_type(buffer) {
+        Some(BufferType::Shm) => with_buffer_contents(buffer, |_, len, data| {
+            data.format == SHM_FORMAT
+                && data.width == size.w
+                && data.height == size.h
+                && data.stride >= size.w * 4
+                && (data.offset + data.stride * data.height) as usize <= len
+        })
+        .unwrap_or(false),
+        Some(BufferType::Dma) => get_dmabuf(buffer).is_ok_and(|dmabuf| {
+            dmabuf.format().code == FOURCC && dmabuf.size() == (size.w, size.h).into()
+        }),
+        _ => false,
+    }
+}
+
+/// Renders `elements` into a client buffer of the given size.
+///
+/// `elements` should be relocated with `Capture::relocate_elements()` and the buffer should be checked with
+/// `is_buffer_valid()`.
+pub(crate) fn render_to_buffer<R, E>(
+    renderer: &mut R,
+    damage_tracker: &mut OutputDamageTracker,
+    size: Size<i32, Physical>,
+    elements: &[E],
+    clear_color: Color32F,
+    buffer: &WlBuffer,
+) -> eyre::Result<()>
+where
+    R: Renderer + ExportMem + Offscreen<GlesTexture> + Bind<GlesTexture> + Bind<Dmabuf>,
+    E: RenderElement<R>,
+{
+    match buffer_type(buffer) {
+        Some(BufferType::Dma) => {
+            let mut dmabuf = get_dmabuf(buffer)
+                .map_err(|e| eyre::eyre!("{}", e))?
+                .clone();
+            let mut fb = renderer
+                .bind(&mut dmabuf)
+                .map_err(|e| eyre::eyre!("{}", e))?;
+            let result = damage_tracker
+                .render_output(renderer, &mut fb, 0, elements, clear_color)
+                .map_err(|e| eyre::eyre!("{:?}", e))?;
+            result.sync.wait().map_err(|e| eyre::eyre!("{:?}", e))?;
+        }
+        Some(BufferType::Shm) => {
+            let buffer_size = (size.w, size.h).into();
+            let mut texture: GlesTexture =
+                Offscreen::<GlesTexture>::create_buffer(renderer, FOURCC, buffer_size)
+                    .map_err(|e| eyre::eyre!("{}", e))?;
+            let mut fb = renderer
+                .bind(&mut texture)
+                .map_err(|e| eyre::eyre!("{}", e))?;
+            damage_tracker
+                .render_output(renderer, &mut fb, 0, elements, clear_color)
+                .map_err(|e| eyre::eyre!("{:?}", e))?;
+            let mapping = renderer
+                .copy_framebuffer(&fb, Rectangle::from_size(buffer_size), FOURCC)
+                .map_err(|e| eyre::eyre!("{}", e))?;
+            let flipped = mapping.flipped();
+            let src = renderer
+                .map_texture(&mapping)
+                .map_err(|e| eyre::eyre!("{}", e))?;
+
+            let row_len = size.w as usize * 4;
+            eyre::ensure!(
+                src.len() >= row_len * size.h as usize,
+                "mapped texture is too small"
+            );
+            with_buffer_contents_mut(buffer, |ptr, len, data| {
+                // safety: `is_buffer_valid()` checked that the rows are in the pool.
+                let dst = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+                for y in 0..size.h as usize {
+                    let src_y = if flipped { size.h as usize - 1 - y } else { y };
+                    let dst_offset = data.offset as usize + data.stride as usize * y;
+                    dst[dst_offset..dst_offset + row_len]
+                        .copy_from_slice(&src[row_len * src_y..row_len * (src_y + 1)]);
+                }
+            })
+            .map_err(|e| eyre::eyre!("{}", e))?;
+        }
+        _ => eyre::bail!("unsupported buffer type"),
+    }
+    Ok(())
+}
+
+impl InnerState {
+    /// Serves pending frames of the output. Backends call it with elements made by `make_output_elements()`.
+    pub(crate) fn render_screencopy<R, E>(
+        &mut self,
+        renderer: &mut R,
+        output: &Output,
+        elements: &[E],
+        cursor_element_count: usize,
+        clear_color: Color32F,
+    ) where
+        R: Renderer + ExportMem + Offscreen<GlesTexture> + Bind<GlesTexture> + Bind<Dmabuf>,
+        E: RenderElement<R>,
+    {
+        use zwlr_screencopy_frame_v1::Flags;
+
+        let state = &mut self.screencopy_state;
+        if !state.pending_frames.iter().any(|pending| {
+            let data = pending.frame.data::<FrameData>().unwrap();
+            data.capture.as_ref().is_some_and(|c| c.output == *output)
+        }) {
+            return;
+        }
+
+        let (frames, rest) = std::mem::take(&mut state.pending_frames)
+            .into_iter()
+            .partition::<Vec<_>, _>(|pending| {
+                let data = pending.frame.data::<FrameData>().unwrap();
+                data.capture.as_ref().is_some_and(|c| c.output == *output)
+            });
+        state.pending_frames = rest;
+
+        let time = Duration::from(self.clock.now());
+        for pending in frames {
+            if !pending.frame.is_alive() {
+                continue;
+            }
+            let data = pending.frame.data::<FrameData>().unwrap();
+            let capture = data.capture.as_ref().unwrap(/* filtered above */);
+            let elements = capture.relocate_elements(elements, cursor_element_count);
+
+            let damage = if pending.with_damage {
+                let Some(damage) = state.damage(&data.manager, capture, &elements) else {
+                    // Wait for the next render.
+                    state.pending_frames.push(pending);
+                    continue;
+                };
+                damage
+            } else {
+                vec![]
+            };
+
+            let size = capture.buffer_size();
+            let mut damage_tracker = capture.make_damage_tracker();
+            match render_to_buffer(
+                renderer,
+                &mut damage_tracker,
+                size,
+                &elements,
+                clear_color,
+                &pending.buffer,
+            ) {
+                Ok(()) => {
+                    pending.frame.flags(Flags::empty());
+                    for rect in damage {
+                        pending.frame.damage(
+                            rect.loc.x as u32,
+                            rect.loc.y as u32,
+                            rect.size.w as u32,
+                            rect.size.h as u32,
+                        );
+                    }
+                    let secs = time.as_secs();
+                    pending
+                        .frame
+                        .ready((secs >> 32) as u32, secs as u32, time.subsec_nanos());
+                }
+                Err(e) => {
+                    warn!("screencopy failed: {:?}", e);
+                    pending.frame.failed();
+                }
+            }
+        }
+    }
+
+    /// Fails frames of removed outputs and forgets destroyed ones.
+    pub fn refresh_screencopy(&mut self) {
+        let outputs = self.space.outputs().cloned().collect::<Vec<_>>();
+        let state = &mut self.screencopy_state;
+        state.pending_frames.retain(|pending| {
+            if !pending.frame.is_alive() {
+                return false;
+            }
+            let data = pending.frame.data::<FrameData>().unwrap();
+            let output = &data.capture.as_ref().unwrap(/* only queued if `Some` */).output;
+            if outputs.contains(output) {
+                return true;
+            }
+            pending.frame.failed();
+            false
+        });
+        state
+            .damage_trackers
+            .retain(|x| x.manager.is_alive() && outputs.contains(&x.capture.output));
+    }
+}
+
+impl GlobalDispatch<ZwlrScreencopyManagerV1, ()> for SabiniwmState {
+    fn bind(
+        _state: &mut Self,
+        _display_handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrScreencopyManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        client
+            .get_data::<ClientState>()
+            .is_none_or(|client_state| client_state.security_context.is_none())
+    }
+}
+
+impl Dispatch<ZwlrScreencopyManagerV1, ()> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrScreencopyManagerV1,
+        request: zwlr_screencopy_manager_v1::Request,
+        _data: &(),
+        _display_handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let (frame, overlay_cursor, output, region) = match request {
+            zwlr_screencopy_manager_v1::Request::CaptureOutput {
+                frame,
+                overlay_cursor,
+                output,
+            } => (frame, overlay_cursor, output, None),
+            zwlr_screencopy_manager_v1::Request::CaptureOutputRegion {
+                frame,
+                overlay_cursor,
+                output,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let region = Rectangle::new((x, y).into(), (width, height).into());
+                (frame, overlay_cursor, output, Some(region))
+            }
+            zwlr_screencopy_manager_v1::Request::Destroy => {
+                state
+                    .inner
+                    .screencopy_state
+                    .damage_trackers
+                    .retain(|x| x.manager != *resource);
+                return;
+            }
+            _ => unreachable!(),
+        };
+        new_frame(
+            resource,
+            frame,
+            overlay_cursor != 0,
+            &output,
+            region,
+            data_init,
+        );
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrScreencopyManagerV1,
+        _data: &(),
+    ) {
+        state
+            .inner
+            .screencopy_state
+            .damage_trackers
+            .retain(|x| x.manager != *resource);
+    }
+}
+
+fn new_frame(
+    manager: &ZwlrScreencopyManagerV1,
+    frame: New<ZwlrScreencopyFrameV1>,
+    overlay_cursor: bool,
+    wl_output: &WlOutput,
+    region: Option<Rectangle<i32, Logical>>,
+    data_init: &mut DataInit<'_, SabiniwmState>,
+) {
+    let capture = Output::from_resource(wl_output)
+        .and_then(|output| Capture::new(output, region, overlay_cursor));
+    let frame = data_init.init(
+        frame,
+        FrameData {
+            manager: manager.clone(),
+            capture: capture.clone(),
+            used: AtomicBool::new(false),
+        },
+    );
+    let Some(capture) = capture else {
+        frame.failed();
+        return;
+    };
+
+    let size = capture.buffer_size();
+    let (w, h) = (size.w as u32, size.h as u32);
+    frame.buffer(SHM_FORMAT, w, h, w * 4);
+    if frame.version() >= 3 {
+        frame.linux_dmabuf(FOURCC as u32, w, h);
+        frame.buffer_done();
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, FrameData> for SabiniwmState {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrScreencopyFrameV1,
+        request: zwlr_screencopy_frame_v1::Request,
+        data: &FrameData,
+        _display_handle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        use zwlr_screencopy_frame_v1::Error;
+
+        let (buffer, with_damage) = match request {
+            zwlr_screencopy_frame_v1::Request::Copy { buffer } => (buffer, false),
+            zwlr_screencopy_frame_v1::Request::CopyWithDamage { buffer } => (buffer, true),
+            zwlr_screencopy_frame_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        if data.used.swap(true, Ordering::Relaxed) {
+            resource.post_error(Error::AlreadyUsed, "frame already used");
+            return;
+        }
+        // `failed` has already been sent.
+        let Some(capture) = &data.capture else {
+            return;
+        };
+        if !is_buffer_valid(&buffer, capture.buffer_size()) {
+            resource.post_error(Error::InvalidBuffer, "invalid buffer");
+            return;
+        }
+
+        state
+            .inner
+            .screencopy_state
+            .pending_frames
+            .push(PendingFrame {
+                frame: resource.clone(),
+                buffer,
+                with_damage,
+            });
+    }
+}
@@ -131,11 +131,18 @@ impl SessionLockHandler for SabiniwmState {
     }
 
     fn lock(&mut self, confirmation: SessionLocker) {
-        self.inner.session_lock_data.lock(confirmation)
+        let was_locked = self.inner.session_lock_data.is_locked();
+        self.inner.session_lock_data.lock(confirmation);
+        if !was_locked {
+            self.inner
+                .emit_ipc_event(sabiniwm_ipc::Event::SessionLocked);
+        }
     }
 
     fn unlock(&mut self) {
         self.inner.session_lock_data.unlock();
+        self.inner
+            .emit_ipc_event(sabiniwm_ipc::Event::SessionUnlocked);
 
         self.update_focus_when_session_lock_changed();
     }
@@ -3,7 +3,7 @@ use crate::state::SabiniwmState;
 use crate::view::window::Window;
 use crate::ClientState;
 use smithay::backend::renderer::utils::on_commit_buffer_handler;
-use smithay::desktop::{layer_map_for_output, LayerSurface};
+use smithay::desktop::{layer_map_for_output, LayerSurface, WindowSurfaceType};
 use smithay::output::Output;
 use smithay::reexports::calloop::Interest;
 use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
@@ -188,6 +188,7 @@ impl CompositorHandler for SabiniwmState {
                     .remove(&surface.id())
                     .unwrap();
                 let window_id = self.inner.view.register_window(window);
+                self.inner.emit_ipc_window_mapped(window_id);
                 self.inner.view.run_manage_hook(
                     &self.inner.config_delegate,
                     window_id,
@@ -250,7 +251,22 @@ impl CompositorHandler for SabiniwmState {
             });
         }
 
-        ensure_initial_configure(surface, &self.inner.space, &mut self.inner.popups)
+        ensure_initial_configure(surface, &self.inner.space, &mut self.inner.popups);
+
+        // A layer surface may change its exclusive zone.
+        let output = self
+            .inner
+            .space
+            .outputs()
+            .find(|o| {
+                layer_map_for_output(o)
+                    .layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
+                    .is_some()
+            })
+            .cloned();
+        if let Some(output) = output {
+            self.inner.on_output_changed(&output);
+        }
     }
 }
 
@@ -269,22 +285,41 @@ impl WlrLayerShellHandler for SabiniwmState {
         let output = wl_output
             .as_ref()
             .and_then(Output::from_resource)
+            .or_else(|| {
+                self.inner
+                    .view
+                    .stackset()
+                    .screens()
+                    .focus()
+                    .map(|screen| screen.output.clone())
+            })
             .unwrap_or_else(|| self.inner.space.outputs().next().unwrap().clone());
-        let mut map = layer_map_for_output(&output);
-        map.map_layer(&LayerSurface::new(surface, namespace))
-            .unwrap();
+        {
+            let mut map = layer_map_for_output(&output);
+            map.map_layer(&LayerSurface::new(surface, namespace))
+                .unwrap();
+            // Drop the lock to the layer map before calling `on_output_changed()`, which accesses the layer map.
+        }
+
+        self.inner.on_output_changed(&output);
     }
 
     fn layer_destroyed(&mut self, surface: WlrLayerSurface) {
-        if let Some((mut map, layer)) = self.inner.space.outputs().find_map(|o| {
+        let mut output = None;
+        if let Some((mut map, layer, o)) = self.inner.space.outputs().find_map(|o| {
             let map = layer_map_for_output(o);
             let layer = map
                 .layers()
                 .find(|&layer| layer.layer_surface() == &surface)
                 .cloned();
-            layer.map(|layer| (map, layer))
+            layer.map(|layer| (map, layer, o.clone()))
         }) {
             map.unmap_layer(&layer);
+            output = Some(o);
+        }
+
+        if let Some(output) = output {
+            self.inner.on_output_changed(&output);
         }
     }
 }
@@ -309,7 +344,7 @@ fn ensure_initial_configure(
     space: &smithay::desktop::Space<Window>,
     popups: &mut smithay::desktop::PopupManager,
 ) {
-    use smithay::desktop::{PopupKind, WindowSurfaceType};
+    use smithay::desktop::PopupKind;
     use smithay::wayland::compositor::{with_surface_tree_upward, TraversalAction};
     use smithay::wayland::shell::wlr_layer::LayerSurfaceData;
     use smithay::wayland::shell::xdg::{XdgPopupSurfaceData, XdgToplevelSurfaceData};
@@ -11,7 +11,7 @@ use smithay::wayland::selection::primary_selection::{
 };
 use smithay::wayland::selection::SelectionTarget;
 use smithay::wayland::xwayland_shell::{XWaylandShellHandler, XWaylandShellState};
-use smithay::xwayland::xwm::{Reorder, ResizeEdge as X11ResizeEdge, XwmId};
+use smithay::xwayland::xwm::{Reorder, ResizeEdge as X11ResizeEdge, WmWindowProperty, XwmId};
 use smithay::xwayland::{X11Surface, X11Wm, XwmHandler};
 use std::os::unix::io::OwnedFd;
 
@@ -36,6 +36,7 @@ impl XwmHandler for SabiniwmState {
 
         let window = smithay::desktop::Window::new_x11_window(window);
         let window_id = self.inner.view.register_window(window);
+        self.inner.emit_ipc_window_mapped(window_id);
         self.inner.view.run_manage_hook(
             &self.inner.config_delegate,
             window_id,
@@ -48,6 +49,7 @@ impl XwmHandler for SabiniwmState {
     fn mapped_override_redirect_window(&mut self, _xwm: XwmId, window: X11Surface) {
         let window = smithay::desktop::Window::new_x11_window(window);
         let window_id = self.inner.view.register_window(window);
+        self.inner.emit_ipc_window_mapped(window_id);
         self.inner.view.set_focus(window_id);
         self.inner.view.layout(&mut self.inner.space);
         self.reflect_focus_from_stackset();
@@ -70,6 +72,13 @@ impl XwmHandler for SabiniwmState {
 
     fn destroyed_window(&mut self, _xwm: XwmId, _window: X11Surface) {}
 
+    fn property_notify(&mut self, _xwm: XwmId, _window: X11Surface, property: WmWindowProperty) {
+        // Titles and classes are reported by foreign toplevel protocols.
+        if matches!(property, WmWindowProperty::Title | WmWindowProperty::Class) {
+            self.inner.view.mark_changed();
+        }
+    }
+
     fn configure_request(
         &mut self,
         _xwm: XwmId,
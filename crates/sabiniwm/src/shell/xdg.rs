@@ -57,6 +57,25 @@ impl XdgShellHandler for SabiniwmState {
         // nop. Currently, resizing windows by drag is not supproted.
     }
 
+    fn minimize_request(&mut self, surface: ToplevelSurface) {
+        let Some(window) = self.window_for_surface(surface.wl_surface()) else {
+            return;
+        };
+        self.inner
+            .view
+            .update_stackset_with(|stackset| stackset.minimize_window(window.id()));
+        self.inner.view.layout(&mut self.inner.space);
+        self.reflect_focus_from_stackset();
+    }
+
+    fn title_changed(&mut self, _surface: ToplevelSurface) {
+        self.inner.view.mark_changed();
+    }
+
+    fn app_id_changed(&mut self, _surface: ToplevelSurface) {
+        self.inner.view.mark_changed();
+    }
+
     fn grab(&mut self, _surface: PopupSurface, _seat: wl_seat::WlSeat, _serial: Serial) {
         // TODO popup grabs
     }
@@ -78,9 +97,18 @@ impl SabiniwmState {
             return;
         };
 
-        let output = self.inner.space.outputs().next().unwrap();
-        let output_geo = self.inner.space.output_geometry(output).unwrap();
         let window_geo = self.inner.space.element_geometry(window).unwrap();
+        let Some(output) = self
+            .inner
+            .space
+            .outputs_for_element(window)
+            .into_iter()
+            .next()
+            .or_else(|| self.inner.space.outputs().next().cloned())
+        else {
+            return;
+        };
+        let output_geo = self.inner.space.output_geometry(&output).unwrap();
 
         // The target geometry for the positioner should be relative to its parent's geometry, so
         // we will compute that here.
@@ -3,8 +3,13 @@ use crate::backend::{Backend, BackendI};
 use crate::config::{ConfigDelegate, ConfigDelegateUnstableI};
 use crate::cursor::Cursor;
 use crate::envvar::EnvVar;
+use crate::ext_workspace::ExtWorkspaceState;
+use crate::foreign_toplevel::ForeignToplevelState;
+use crate::image_copy_capture::ImageCopyCaptureState;
 use crate::input::{KeySeq, Keymap, ModMask};
 use crate::input_event::FocusUpdateDecider;
+use crate::ipc::IpcServer;
+use crate::screencopy::ScreencopyState;
 use crate::util::EventHandler;
 use crate::view::view::View;
 use crate::view::window::Window;
@@ -91,6 +96,10 @@ pub(crate) struct InnerState {
     #[allow(unused)]
     pub single_pixel_buffer_state: SinglePixelBufferState,
     pub session_lock_data: crate::session_lock::SessionLockData,
+    pub ext_workspace_state: ExtWorkspaceState,
+    pub foreign_toplevel_state: ForeignToplevelState,
+    pub screencopy_state: ScreencopyState,
+    pub image_copy_capture_state: ImageCopyCaptureState,
     #[allow(unused)]
     pub fifo_manager_state: FifoManagerState,
     #[allow(unused)]
@@ -115,6 +124,7 @@ pub(crate) struct InnerState {
     pub modmask_state: ModMask,
     pub keyseq: KeySeq,
     pub view: View,
+    pub ipc_server: Option<IpcServer>,
     pub focus_update_decider: FocusUpdateDecider,
 
     pub config_delegate: ConfigDelegate,
@@ -136,9 +146,12 @@ where
 
 impl SabiniwmState {
     pub fn run(config_delegate: Box<dyn ConfigDelegateUnstableI>) -> eyre::Result<()> {
+        #[cfg(feature = "headless")]
+        use crate::backend::headless::HeadlessBackend;
         use crate::backend::udev::UdevBackend;
         #[cfg(feature = "winit")]
         use crate::backend::winit::WinitBackend;
+        use crate::envvar::BackendName;
 
         let envvar = EnvVar::load()?;
 
@@ -146,18 +159,35 @@ impl SabiniwmState {
 
         let event_loop = EventLoop::try_new().unwrap();
 
-        let use_udev = envvar.generic.display.is_none() && envvar.generic.wayland_display.is_none();
+        let backend_name = envvar.sabiniwm.backend.unwrap_or_else(|| {
+            if envvar.generic.display.is_none() && envvar.generic.wayland_display.is_none() {
+                BackendName::Udev
+            } else {
+                BackendName::Winit
+            }
+        });
 
-        let backend = if use_udev {
-            UdevBackend::new(&envvar, event_loop.handle().clone())?.into()
-        } else {
-            #[cfg(feature = "winit")]
-            {
-                WinitBackend::new(event_loop.handle().clone())?.into()
+        let backend = match backend_name {
+            BackendName::Udev => UdevBackend::new(&envvar, event_loop.handle().clone())?.into(),
+            BackendName::Winit => {
+                #[cfg(feature = "winit")]
+                {
+                    WinitBackend::new(event_loop.handle().clone())?.into()
+                }
+                #[cfg(not(feature = "winit"))]
+                {
+                    eyre::bail!("winit backend is not enabled");
+                }
             }
-            #[cfg(not(feature = "winit"))]
-            {
-                unreachable!();
+            BackendName::Headless => {
+                #[cfg(feature = "headless")]
+                {
+                    HeadlessBackend::new(&envvar, event_loop.handle().clone())?.into()
+                }
+                #[cfg(not(feature = "headless"))]
+                {
+                    eyre::bail!("headless backend is not enabled");
+                }
             }
         };
 
@@ -226,6 +256,14 @@ impl SabiniwmState {
             socket_name
         );
 
+        let ipc_server = match IpcServer::new(&loop_handle, &socket_name) {
+            Ok(ipc_server) => Some(ipc_server),
+            Err(e) => {
+                warn!("Failed to start IPC server. Running without IPC: {}", e);
+                None
+            }
+        };
+
         // init globals
         let compositor_state = CompositorState::new::<Self>(&display_handle);
         let data_device_state = DataDeviceState::new::<Self>(&display_handle);
@@ -243,6 +281,14 @@ impl SabiniwmState {
         let xdg_foreign_state = XdgForeignState::new::<Self>(&display_handle);
         let single_pixel_buffer_state = SinglePixelBufferState::new::<Self>(&display_handle);
         let session_lock_data = crate::session_lock::SessionLockData::new(&display_handle);
+        let ext_workspace_state = ExtWorkspaceState::new(&display_handle);
+        let foreign_toplevel_state = ForeignToplevelState::new(&display_handle);
+        let screencopy_state = ScreencopyState::default();
+        let image_copy_capture_state = ImageCopyCaptureState::default();
+        if backend.has_screen_capture() {
+            ScreencopyState::create_global(&display_handle);
+            ImageCopyCaptureState::create_globals(&display_handle);
+        }
         let fifo_manager_state = FifoManagerState::new::<Self>(&display_handle);
         let commit_timing_manager_state = CommitTimingManagerState::new::<Self>(&display_handle);
         TextInputManagerState::new::<Self>(&display_handle);
@@ -309,8 +355,7 @@ impl SabiniwmState {
         let modmask = config_delegate.get_modmask(backend.is_udev());
         let keymap = config_delegate.make_keymap(backend.is_udev());
 
-        let rect = Rectangle::from_size((1280, 720).into());
-        let view = View::new(&config_delegate, rect);
+        let view = View::new(&config_delegate);
 
         Ok(SabiniwmState {
             backend,
@@ -334,6 +379,10 @@ impl SabiniwmState {
                 xdg_foreign_state,
                 single_pixel_buffer_state,
                 session_lock_data,
+                ext_workspace_state,
+                foreign_toplevel_state,
+                screencopy_state,
+                image_copy_capture_state,
                 fifo_manager_state,
                 commit_timing_manager_state,
                 dnd_icon: None,
@@ -352,6 +401,7 @@ impl SabiniwmState {
                 modmask_state: ModMask::default(),
                 keyseq: KeySeq::new(),
                 view,
+                ipc_server,
                 focus_update_decider: FocusUpdateDecider::new(),
 
                 config_delegate,
@@ -361,10 +411,18 @@ impl SabiniwmState {
 
     fn run_loop(&mut self, mut event_loop: EventLoop<'_, SabiniwmState>) -> eyre::Result<()> {
         event_loop.run(None, self, |state| {
-            let should_reflect = state.inner.view.refresh(&mut state.inner.space);
-            if should_reflect {
+            let removed_window_ids = state.inner.view.refresh(&mut state.inner.space);
+            if !removed_window_ids.is_empty() {
+                for window_id in removed_window_ids {
+                    state.inner.emit_ipc_window_unmapped(window_id);
+                }
                 state.reflect_focus_from_stackset();
             }
+            state.inner.emit_ipc_title_events();
+            state.inner.refresh_ext_workspace();
+            state.inner.refresh_foreign_toplevel();
+            state.inner.refresh_screencopy();
+            state.inner.refresh_image_copy_capture();
 
             state.inner.space.refresh();
             state.inner.popups.cleanup();
@@ -376,9 +434,60 @@ impl SabiniwmState {
 }
 
 impl InnerState {
+    /// Must be called after `Space::map_output()`.
     pub fn on_output_added(&mut self, output: &smithay::output::Output) {
         self.session_lock_data.on_output_added(output);
+
+        let rect = self.tiling_rect_for_output(output);
+        update_tiling_rect_cache(output, rect);
+        self.view.add_output(output.clone(), rect, &mut self.space);
+    }
+
+    /// Must be called before `Space::unmap_output()`.
+    pub fn on_output_removed(&mut self, output: &smithay::output::Output) {
+        self.view.remove_output(output, &mut self.space);
+    }
+
+    /// Must be called when the output geometry or exclusive zones of layer surfaces on the output are changed.
+    ///
+    /// Must be called after `Space::map_output()` for the new mode/position.
+    pub fn on_output_changed(&mut self, output: &smithay::output::Output) {
+        let rect = self.tiling_rect_for_output(output);
+        // Layer surfaces commit frequently, e.g. status bars, and most commits don't change exclusive zones.
+        if !update_tiling_rect_cache(output, rect) {
+            return;
+        }
+        self.view.resize_output(output, rect, &mut self.space);
+    }
+
+    fn tiling_rect_for_output(&self, output: &smithay::output::Output) -> Rectangle<i32, Logical> {
+        let output_geometry =
+            self.space.output_geometry(output).unwrap(/* Space::map_output() is called. */);
+
+        let mut map = smithay::desktop::layer_map_for_output(output);
+        // Reflect the current output size.
+        map.arrange();
+        // Relative to the output.
+        let mut rect = map.non_exclusive_zone();
+        rect.loc += output_geometry.loc;
+        rect
+    }
+}
+
+/// Tiling rect of an output that is last passed to the view. Stored in the user data of the output.
+struct TilingRectCache(std::cell::Cell<Rectangle<i32, Logical>>);
+
+/// Returns true iff the cached tiling rect of the output is changed.
+fn update_tiling_rect_cache(
+    output: &smithay::output::Output,
+    rect: Rectangle<i32, Logical>,
+) -> bool {
+    let user_data = output.user_data();
+    if user_data.insert_if_missing(|| TilingRectCache(std::cell::Cell::new(rect))) {
+        return true;
     }
+    let cache = user_data.get::<TilingRectCache>().unwrap(/* Inserted above. */);
+    cache.0.replace(rect) != rect
 }
 
 impl EventHandler<XWaylandEvent> for SabiniwmState {
@@ -413,3 +522,91 @@ impl EventHandler<XWaylandEvent> for SabiniwmState {
         }
     }
 }
+
+#[cfg(all(test, feature = "headless"))]
+mod tests {
+    use super::*;
+    use crate::backend::headless::HeadlessBackend;
+    use crate::config::ConfigDelegateUnstableDefault;
+    use crate::envvar::{BackendName, EnvVarGeneric, EnvVarSabiniwm};
+
+    /// Set in the child process spawned by `test_start_headless()`.
+    const TEST_START_HEADLESS_CHILD: &str = "SABINIWM_TEST_START_HEADLESS_CHILD";
+
+    #[test]
+    fn test_start_headless() {
+        if std::env::var_os(TEST_START_HEADLESS_CHILD).is_some() {
+            start_headless();
+            return;
+        }
+
+        // `SabiniwmState::new()` spawns XWayland.
+        std::process::Command::new("Xwayland")
+            .arg("-version")
+            .output()
+            .expect("Xwayland is required to run this test");
+
+        // `SabiniwmState` uses and sets process-global environment variables, e.g. `XDG_RUNTIME_DIR` and
+        // `WAYLAND_DISPLAY`. Run it in its own process so that it doesn't race with other tests.
+        let runtime_dir =
+            std::env::temp_dir().join(format!("sabiniwm-test-{}", std::process::id()));
+        std::fs::create_dir_all(&runtime_dir).unwrap();
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "state::tests::test_start_headless",
+                "--exact",
+                "--test-threads=1",
+            ])
+            .env(TEST_START_HEADLESS_CHILD, "1")
+            .env("XDG_RUNTIME_DIR", &runtime_dir)
+            .env_remove("WAYLAND_DISPLAY")
+            .env_remove("DISPLAY")
+            .status()
+            .unwrap();
+        std::fs::remove_dir_all(&runtime_dir).unwrap();
+        assert!(status.success());
+    }
+
+    fn start_headless() {
+        let envvar = EnvVar {
+            generic: EnvVarGeneric {
+                display: None,
+                wayland_display: None,
+            },
+            sabiniwm: EnvVarSabiniwm {
+                backend: Some(BackendName::Headless),
+                drm_device_node: None,
+                disable_10bit: false,
+                enable_direct_scanout: true,
+                headless_outputs: "1920x1080,1280x720".to_string(),
+                headless_render: true,
+            },
+        };
+        let mut event_loop = EventLoop::try_new().unwrap();
+        let backend = HeadlessBackend::new(&envvar, event_loop.handle()).unwrap();
+        let mut state = SabiniwmState::new(
+            ConfigDelegate::new(Box::new(ConfigDelegateUnstableDefault)),
+            envvar,
+            event_loop.handle(),
+            event_loop.get_signal(),
+            backend.into(),
+        )
+        .unwrap();
+        state.backend.init(&mut state.inner).unwrap();
+
+        // Let render loops render some frames.
+        for _ in 0..3 {
+            event_loop
+                .dispatch(Some(Duration::from_millis(20)), &mut state)
+                .unwrap();
+        }
+
+        assert_eq!(state.inner.space.outputs().count(), 2);
+        let screens = state.inner.view.stackset().screens();
+        assert_eq!(screens.len(), 2);
+        assert_eq!(
+            screens.as_vec()[1].rect,
+            Rectangle::new((1920, 0).into(), (1280, 720).into())
+        );
+    }
+}
@@ -7,10 +7,12 @@ use smithay::desktop::{PopupKind, PopupManager};
 use smithay::input::keyboard::LedState;
 use smithay::input::pointer::{CursorImageStatus, PointerHandle};
 use smithay::input::{Seat, SeatHandler, SeatState};
+use smithay::output::Output;
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::
     zxdg_toplevel_decoration_v1::Mode as DecorationMode;
 use smithay::reexports::wayland_protocols::xdg::decoration::{self as xdg_decoration};
 use smithay::reexports::wayland_server::protocol::wl_data_source::WlDataSource;
+use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::Resource;
 use smithay::utils::Rectangle;
@@ -94,7 +96,16 @@ impl ServerDndGrabHandler for SabiniwmState {
 
 smithay::delegate_data_device!(SabiniwmState);
 
-impl OutputHandler for SabiniwmState {}
+impl OutputHandler for SabiniwmState {
+    fn output_bound(&mut self, output: Output, wl_output: WlOutput) {
+        self.inner
+            .ext_workspace_state
+            .on_output_bound(&output, &wl_output);
+        self.inner
+            .foreign_toplevel_state
+            .on_output_bound(&output, &wl_output);
+    }
+}
 
 smithay::delegate_output!(SabiniwmState);
 
@@ -327,6 +338,18 @@ impl XdgActivationHandler for SabiniwmState {
                 .cloned();
             if let Some(window) = w {
                 self.inner.space.raise_element(&window, true);
+                return;
+            }
+
+            // The window is not visible. Mark it as urgent instead of switching workspaces without user's intention.
+            let window_id = self
+                .inner
+                .view
+                .windows()
+                .find(|window| window.smithay_window().wl_surface().as_deref() == Some(&surface))
+                .map(|window| window.id());
+            if let Some(window_id) = window_id {
+                self.inner.view.set_urgent(window_id, true);
             }
         }
     }
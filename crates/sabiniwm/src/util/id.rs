@@ -22,13 +22,16 @@ impl<T> Id<T> {
         }
     }
 
-    #[allow(dead_code)]
     pub fn from(value: u64) -> Self {
         Id {
             value,
             _phantom: PhantomData,
         }
     }
+
+    pub fn as_u64(&self) -> u64 {
+        self.value
+    }
 }
 
 impl<T> Hash for Id<T> {
@@ -1,14 +1,23 @@
-use crate::util::Id;
+use crate::util::{FocusedVec, Id};
+use crate::view::decoration::Decoration;
 use crate::view::layout_node::{LayoutMessage, LayoutNode, LayoutTree};
 use crate::view::stackset::Workspace;
-use crate::view::window::{Border, Rgba, Window, WindowProps};
+use crate::view::window::{Border, Rgba, SizeHints, Window, WindowProps};
 use smithay::utils::{Logical, Rectangle};
+use std::collections::HashMap;
 
 pub struct ViewLayoutApi<'a> {
     pub(super) workspace: &'a Workspace,
+    pub(super) windows: &'a HashMap<Id<Window>, Window>,
+    pub(super) stack: &'a FocusedVec<Id<Window>>,
     pub(super) layout_tree: &'a mut LayoutTree,
     pub(super) layout_queue: &'a mut Vec<(Id<Window>, WindowProps)>,
+    pub(super) decoration_queue: &'a mut Vec<Decoration>,
     pub(super) rect: Rectangle<i32, Logical>,
+    // Windows in `layout_queue` and decorations in `decoration_queue` before these indices are laid out by other nodes,
+    // e.g. siblings.
+    pub(super) layout_queue_start: usize,
+    pub(super) decoration_queue_start: usize,
 }
 
 impl ViewLayoutApi<'_> {
@@ -16,20 +25,60 @@ impl ViewLayoutApi<'_> {
         self.workspace
     }
 
+    /// Windows to be laid out by the node.
+    ///
+    /// It is the stack of the workspace unless a parent node narrows it with `layout_node_with_stack()`.
+    pub fn stack(&self) -> &FocusedVec<Id<Window>> {
+        self.stack
+    }
+
+    pub fn window(&self, id: Id<Window>) -> Option<&Window> {
+        self.windows.get(&id)
+    }
+
+    /// Area assigned to the node.
+    ///
+    /// Windows and child nodes may be placed outside of it, e.g. by `LayoutScrollColumn`. Windows entirely off the
+    /// screen are not shown, nor are ones partially off the screen that would intrude on another output.
     pub fn rect(&self) -> &Rectangle<i32, Logical> {
         &self.rect
     }
 
+    /// Size hints of the window. `View` clamps the window to them and centers it in the given geometry, so layouts
+    /// can use them to avoid gaps.
+    pub fn size_hints(&self, id: Id<Window>) -> SizeHints {
+        self.windows
+            .get(&id)
+            .map(|window| window.size_hints())
+            .unwrap_or_default()
+    }
+
     pub fn layout_node(&mut self, id: Id<LayoutNode>, rect: Rectangle<i32, Logical>) {
-        assert!(self.rect.contains_rect(rect));
+        let stack = self.stack;
+        self.layout_node_with_stack(id, rect, stack);
+    }
 
+    /// Same as `layout_node()`, but the child lays out only windows in `stack`, e.g. a subset of `self.stack()`.
+    pub fn layout_node_with_stack(
+        &mut self,
+        id: Id<LayoutNode>,
+        rect: Rectangle<i32, Logical>,
+        stack: &FocusedVec<Id<Window>>,
+    ) {
         // Safety: `LayoutTree` doesn't allow recursive structure.
         let node = unsafe { self.layout_tree.borrow_node(id) };
+        let layout_queue_start = self.layout_queue.len();
+        let decoration_queue_start = self.decoration_queue.len();
         let mut api = ViewLayoutApi {
             workspace: self.workspace,
+            windows: self.windows,
+            stack,
             layout_tree: self.layout_tree,
             layout_queue: self.layout_queue,
+            decoration_queue: self.decoration_queue,
             rect,
+            layout_queue_start,
+            decoration_queue_start,
         };
         node.layout(&mut api);
     }
@@ -50,19 +99,48 @@ impl ViewLayoutApi<'_> {
         self.layout_queue.push((id, props));
     }
 
+    /// Registers a decoration drawn below windows, e.g. a tab bar.
+    pub fn add_decoration(&mut self, decoration: Decoration) {
+        self.decoration_queue.push(decoration);
+    }
+
     pub fn modify_layout_queue_with<F>(&mut self, f: F)
     where
         F: Fn(&mut Vec<(Id<Window>, WindowProps)>),
     {
         f(self.layout_queue);
     }
+
+    /// Same as `modify_layout_queue_with()`, but only for windows laid out by this node so far, i.e. by its children.
+    /// Windows laid out by other nodes, e.g. siblings in `LayoutNodeCombine`, are not included.
+    pub fn modify_own_layout_queue_with<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut [(Id<Window>, WindowProps)]),
+    {
+        f(&mut self.layout_queue[self.layout_queue_start..]);
+    }
+
+    /// Same as `modify_own_layout_queue_with()`, but for decorations. Nodes that move or resize windows should apply
+    /// the same changes to decorations so that e.g. tab bars follow their windows.
+    pub fn modify_decoration_queue_with<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut [Decoration]),
+    {
+        f(&mut self.decoration_queue[self.decoration_queue_start..]);
+    }
 }
 
 pub struct ViewHandleMessageApi<'a> {
+    pub(super) stack: &'a FocusedVec<Id<Window>>,
     pub(super) layout_tree: &'a mut LayoutTree,
 }
 
 impl ViewHandleMessageApi<'_> {
+    /// Stack of the workspace, e.g. to get the focused window.
+    pub fn stack(&self) -> &FocusedVec<Id<Window>> {
+        self.stack
+    }
+
     pub fn handle_message(
         &mut self,
         id: Id<LayoutNode>,
@@ -78,3 +156,19 @@ impl ViewHandleMessageApi<'_> {
         self.handle_message(root_id, message);
     }
 }
+
+pub struct ViewDescribeApi<'a> {
+    pub(super) layout_tree: &'a LayoutTree,
+}
+
+impl ViewDescribeApi<'_> {
+    pub fn describe_node(&self, id: Id<LayoutNode>) -> String {
+        // Safety: `LayoutTree` doesn't allow recursive structure and describing doesn't mutate nodes.
+        let node = unsafe { self.layout_tree.borrow_node(id) };
+        node.description(self)
+    }
+
+    pub(super) fn describe_root(&self) -> String {
+        self.describe_node(self.layout_tree.root_id())
+    }
+}
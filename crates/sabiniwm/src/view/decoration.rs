@@ -0,0 +1,151 @@
+//! Decorations drawn by layout nodes, e.g. tab bars of `LayoutTabbed`.
+
+use crate::render::CustomRenderElement;
+use crate::util::bitmap_font::{self, GLYPH_HEIGHT};
+use crate::util::Id;
+use crate::view::window::{Rgba, Window};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::memory::{
+    MemoryRenderBuffer, MemoryRenderBufferRenderElement,
+};
+use smithay::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::{ImportAll, ImportMem, Renderer, Texture};
+use smithay::utils::{Logical, Physical, Point, Rectangle, Scale, Size, Transform};
+use std::collections::HashMap;
+
+const LABEL_PADDING: i32 = 4;
+
+/// A rectangle with an optional label, registered by `ViewLayoutApi::add_decoration()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoration {
+    pub rect: Rectangle<i32, Logical>,
+    pub background_rgba: Rgba,
+    pub label: Option<DecorationLabel>,
+    pub label_rgba: Rgba,
+    /// Window that is focused when the decoration is clicked.
+    pub focus_on_click: Option<Id<Window>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecorationLabel {
+    Text(String),
+    /// Title of the window. It follows changes of the title without relayout.
+    WindowTitle(Id<Window>),
+}
+
+/// `Decoration` with buffers to render, which are kept while the decoration is unchanged so that
+/// damage tracking works.
+pub(crate) struct DecorationElement {
+    decoration: Decoration,
+    background: SolidColorBuffer,
+    label: Option<Label>,
+}
+
+struct Label {
+    text: String,
+    buffer: MemoryRenderBuffer,
+    size: Size<i32, Logical>,
+}
+
+impl DecorationElement {
+    pub fn new(decoration: Decoration, windows: &HashMap<Id<Window>, Window>) -> Self {
+        let background = SolidColorBuffer::new(
+            decoration.rect.size,
+            decoration.background_rgba.to_f32_array(),
+        );
+        let mut ret = Self {
+            decoration,
+            background,
+            label: None,
+        };
+        ret.refresh_label(windows);
+        ret
+    }
+
+    pub fn decoration(&self) -> &Decoration {
+        &self.decoration
+    }
+
+    /// Re-renders the label if its text is changed.
+    pub fn refresh_label(&mut self, windows: &HashMap<Id<Window>, Window>) {
+        let text = match &self.decoration.label {
+            None => None,
+            Some(DecorationLabel::Text(text)) => Some(text.clone()),
+            Some(DecorationLabel::WindowTitle(window_id)) => windows
+                .get(window_id)
+                .map(|window| window.title().unwrap_or_default()),
+        };
+        let Some(text) = text else {
+            self.label = None;
+            return;
+        };
+        if self.label.as_ref().is_some_and(|label| label.text == text) {
+            return;
+        }
+
+        let rect = &self.decoration.rect;
+        let scale = (0.max(rect.size.h - LABEL_PADDING) as usize / GLYPH_HEIGHT).max(1);
+        let max_width = 0.max(rect.size.w - 2 * LABEL_PADDING) as usize;
+        let image = bitmap_font::render_text(&text, &self.decoration.label_rgba, scale, max_width);
+        self.label = image.map(|image| {
+            let size = Size::from((image.width as i32, image.height as i32));
+            let buffer = MemoryRenderBuffer::from_slice(
+                &image.pixels,
+                Fourcc::Argb8888,
+                (image.width as i32, image.height as i32),
+                1,
+                Transform::Normal,
+                None,
+            );
+            Label { text, buffer, size }
+        });
+    }
+
+    /// `output_loc` is the location of the output in the global coordinates.
+    pub fn render_elements<R>(
+        &self,
+        renderer: &mut R,
+        output_loc: Point<i32, Logical>,
+        scale: Scale<f64>,
+    ) -> Vec<CustomRenderElement<R>>
+    where
+        R: Renderer + ImportAll + ImportMem,
+        R::TextureId: Texture + Clone + Send + 'static,
+    {
+        let rect = &self.decoration.rect;
+        let mut ret = vec![];
+
+        if let Some(label) = &self.label {
+            let loc = rect.loc
+                + Point::from((LABEL_PADDING, 0.max(rect.size.h - label.size.h) / 2))
+                - output_loc;
+            let loc: Point<i32, Physical> = loc.to_physical_precise_round(scale);
+            match MemoryRenderBufferRenderElement::from_buffer(
+                renderer,
+                loc.to_f64(),
+                &label.buffer,
+                None,
+                None,
+                None,
+                Kind::Unspecified,
+            ) {
+                Ok(element) => ret.push(CustomRenderElement::DecorationLabel(element)),
+                Err(e) => warn!("failed to render a decoration label: {:?}", e),
+            }
+        }
+
+        let loc: Point<i32, Physical> = (rect.loc - output_loc).to_physical_precise_round(scale);
+        ret.push(CustomRenderElement::Decoration(
+            SolidColorRenderElement::from_buffer(
+                &self.background,
+                loc,
+                scale,
+                1.0,
+                Kind::Unspecified,
+            ),
+        ));
+
+        ret
+    }
+}
@@ -1,5 +1,5 @@
 use crate::util::Id;
-use crate::view::api::{ViewHandleMessageApi, ViewLayoutApi};
+use crate::view::api::{ViewDescribeApi, ViewHandleMessageApi, ViewLayoutApi};
 use downcast::Any;
 use dyn_clone::DynClone;
 use std::cell::UnsafeCell;
@@ -44,6 +44,15 @@ pub trait LayoutNodeI: dyn_clone::DynClone {
     ) -> std::ops::ControlFlow<()> {
         std::ops::ControlFlow::Continue(())
     }
+
+    /// Describes the current state of the layout for bars and IPC, e.g. "Tall".
+    ///
+    /// Nodes with children should compose descriptions of them with `ViewDescribeApi::describe_node()`. The default
+    /// implementation is the type name.
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
 }
 
 dyn_clone::clone_trait_object!(LayoutNodeI);
@@ -82,6 +91,10 @@ impl LayoutNode {
     ) -> std::ops::ControlFlow<()> {
         self.inner.handle_message(api, message)
     }
+
+    pub fn description(&self, api: &ViewDescribeApi<'_>) -> String {
+        self.inner.description(api)
+    }
 }
 
 #[derive(Clone)]
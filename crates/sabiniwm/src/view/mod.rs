@@ -1,6 +1,8 @@
 pub mod api;
+pub mod decoration;
 pub mod layout_node;
 pub mod predefined;
+pub mod scratchpad;
 pub mod stackset;
 #[allow(clippy::module_inception)]
 pub mod view;
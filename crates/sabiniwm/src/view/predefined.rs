@@ -1,44 +1,709 @@
 use crate::model::grid_geometry::{RectangleExt, SplitSpec};
-use crate::util::{Id, NonEmptyFocusedVec};
-use crate::view::api::{ViewHandleMessageApi, ViewLayoutApi};
+use crate::model::split_tree::{Container, ContainerKind, MoveDirection, SplitTree, SplitTreeNode};
+use crate::util::{FocusedVec, Id, NonEmptyFocusedVec};
+use crate::view::api::{ViewDescribeApi, ViewHandleMessageApi, ViewLayoutApi};
+use crate::view::decoration::{Decoration, DecorationLabel};
 use crate::view::layout_node::{LayoutMessage, LayoutMessageI, LayoutNode, LayoutNodeI};
-use crate::view::window::{Border, Thickness};
+use crate::view::window::{Border, Rgba, Thickness, Window, WindowProps};
 pub use itertools::izip;
+use smithay::utils::{Logical, Rectangle};
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct LayoutFull {}
 
 impl LayoutNodeI for LayoutFull {
     fn layout(&self, api: &mut ViewLayoutApi<'_>) {
-        if let Some(&window_id) = api.workspace().stack().focus() {
+        if let Some(&window_id) = api.stack().focus() {
             api.layout_window(window_id, *api.rect());
         }
     }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        "Full".to_string()
+    }
+}
+
+/// Increments (or decrements if negative) the number of windows in the master area.
+#[derive(Debug, Clone)]
+pub struct LayoutMessageIncMaster(pub i32);
+
+impl LayoutMessageI for LayoutMessageIncMaster {}
+
+/// Shrinks or expands the master area.
+#[derive(Debug, Clone)]
+pub enum LayoutMessageResize {
+    Shrink,
+    Expand,
 }
 
+impl LayoutMessageI for LayoutMessageResize {}
+
+/// Resets parameters of a layout to the initial ones.
+#[derive(Debug, Clone)]
+pub struct LayoutMessageReset;
+
+impl LayoutMessageI for LayoutMessageReset {}
+
+/// Parameters of layouts with the master area, which are changed by `LayoutMessageIncMaster`,
+/// `LayoutMessageResize` and `LayoutMessageReset`.
+#[derive(Debug, Clone)]
+struct MasterParams {
+    nmaster: usize,
+    ratio: f32,
+    ratio_delta: f32,
+    initial_nmaster: usize,
+    initial_ratio: f32,
+}
+
+impl MasterParams {
+    fn new(nmaster: usize, ratio: f32, ratio_delta: f32) -> Self {
+        let ratio = ratio.clamp(0.0, 1.0);
+        Self {
+            nmaster,
+            ratio,
+            ratio_delta,
+            initial_nmaster: nmaster,
+            initial_ratio: ratio,
+        }
+    }
+
+    /// Splits windows into the master ones and the others.
+    fn split_windows(&self, api: &ViewLayoutApi<'_>) -> (Vec<Id<Window>>, Vec<Id<Window>>) {
+        let mut master = api.stack().as_vec().clone();
+        let stack = master.split_off(self.nmaster.min(master.len()));
+        (master, stack)
+    }
+
+    fn handle_message(&mut self, message: &LayoutMessage) -> std::ops::ControlFlow<()> {
+        if let Some(LayoutMessageIncMaster(delta)) =
+            message.downcast_ref::<LayoutMessageIncMaster>()
+        {
+            self.nmaster = self.nmaster.saturating_add_signed(*delta as isize);
+            return std::ops::ControlFlow::Break(());
+        }
+
+        if let Some(message) = message.downcast_ref::<LayoutMessageResize>() {
+            let delta = match message {
+                LayoutMessageResize::Shrink => -self.ratio_delta,
+                LayoutMessageResize::Expand => self.ratio_delta,
+            };
+            self.ratio = (self.ratio + delta).clamp(0.0, 1.0);
+            return std::ops::ControlFlow::Break(());
+        }
+
+        if message.downcast_ref::<LayoutMessageReset>().is_some() {
+            self.nmaster = self.initial_nmaster;
+            self.ratio = self.initial_ratio;
+            return std::ops::ControlFlow::Break(());
+        }
+
+        std::ops::ControlFlow::Continue(())
+    }
+}
+
+/// Lays out windows in a column from top to bottom.
+fn layout_column(
+    api: &mut ViewLayoutApi<'_>,
+    windows: Vec<Id<Window>>,
+    rect: Rectangle<i32, Logical>,
+) {
+    let rects = rect.split_horizontally(&vec![SplitSpec::Elastic; windows.len()]);
+    for (window_id, rect) in izip!(windows, rects) {
+        api.layout_window(window_id, rect);
+    }
+}
+
+/// Lays out master windows in the left column and the others in the right column.
+fn layout_master_and_stack(
+    api: &mut ViewLayoutApi<'_>,
+    master: Vec<Id<Window>>,
+    stack: Vec<Id<Window>>,
+    rect: Rectangle<i32, Logical>,
+    ratio: f32,
+) {
+    match (master.is_empty(), stack.is_empty()) {
+        (true, true) => {}
+        (true, false) => layout_column(api, stack, rect),
+        (false, true) => layout_column(api, master, rect),
+        (false, false) => {
+            let [master_rect, stack_rect] =
+                rect.split_vertically_2([SplitSpec::Ratio(ratio), SplitSpec::Elastic]);
+            layout_column(api, master, master_rect);
+            layout_column(api, stack, stack_rect);
+        }
+    }
+}
+
+/// Layout with the master area on the left and the stack area on the right, like xmonad's `Tall`.
 #[derive(Clone)]
-pub struct LayoutTall {}
+pub struct LayoutTall {
+    params: MasterParams,
+}
+
+impl LayoutTall {
+    /// - `nmaster`: The number of windows in the master area.
+    /// - `ratio`: The ratio of the width of the master area to the whole.
+    /// - `ratio_delta`: The amount of ratio changed by `LayoutMessageResize`.
+    pub fn new(nmaster: usize, ratio: f32, ratio_delta: f32) -> Self {
+        Self {
+            params: MasterParams::new(nmaster, ratio, ratio_delta),
+        }
+    }
+}
+
+impl Default for LayoutTall {
+    fn default() -> Self {
+        Self::new(1, 0.5, 0.03)
+    }
+}
 
 impl LayoutNodeI for LayoutTall {
     fn layout(&self, api: &mut ViewLayoutApi<'_>) {
-        let mut head = api.workspace.stack().as_vec().clone();
-        match head.len() {
-            0 => {}
-            1 => {
-                api.layout_window(head[0], *api.rect());
-            }
-            _ => {
-                let tail = head.split_off(1);
-                let [head_rect, tail_rect] = api
-                    .rect()
-                    .split_vertically_2([SplitSpec::Elastic, SplitSpec::Elastic]);
-                api.layout_window(head[0], head_rect);
-                let tail_rect = tail_rect.split_horizontally(&vec![SplitSpec::Elastic; tail.len()]);
-                for (window_id, rect) in izip!(tail, tail_rect) {
-                    api.layout_window(window_id, rect);
+        let (master, stack) = self.params.split_windows(api);
+        let rect = *api.rect();
+        layout_master_and_stack(api, master, stack, rect, self.params.ratio);
+    }
+
+    fn handle_message(
+        &mut self,
+        _api: &mut ViewHandleMessageApi<'_>,
+        message: &LayoutMessage,
+    ) -> std::ops::ControlFlow<()> {
+        self.params.handle_message(message)
+    }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        "Tall".to_string()
+    }
+}
+
+/// Layout with the master column and two stack columns, like xmonad's `ThreeCol`/`ThreeColMid`.
+///
+/// Stack windows are distributed to two columns, the first half to the first column. If there is only
+/// one stack window, the layout is the same as `LayoutTall`.
+#[derive(Clone)]
+pub struct LayoutThreeColumn {
+    params: MasterParams,
+    master_in_middle: bool,
+}
+
+impl LayoutThreeColumn {
+    /// - `nmaster`: The number of windows in the master column.
+    /// - `ratio`: The ratio of the width of the master column to the whole.
+    /// - `ratio_delta`: The amount of ratio changed by `LayoutMessageResize`.
+    /// - `master_in_middle`: Whether to place the master column in the middle or on the left.
+    pub fn new(nmaster: usize, ratio: f32, ratio_delta: f32, master_in_middle: bool) -> Self {
+        Self {
+            params: MasterParams::new(nmaster, ratio, ratio_delta),
+            master_in_middle,
+        }
+    }
+}
+
+impl Default for LayoutThreeColumn {
+    fn default() -> Self {
+        Self::new(1, 0.5, 0.03, true)
+    }
+}
+
+impl LayoutNodeI for LayoutThreeColumn {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        use SplitSpec::{Elastic, Ratio};
+
+        let (master, mut stack0) = self.params.split_windows(api);
+        let rect = *api.rect();
+        if master.is_empty() || stack0.len() <= 1 {
+            layout_master_and_stack(api, master, stack0, rect, self.params.ratio);
+            return;
+        }
+
+        let stack1 = stack0.split_off(stack0.len().div_ceil(2));
+        let ratio = Ratio(self.params.ratio);
+        let (master_rect, stack0_rect, stack1_rect) = if self.master_in_middle {
+            let [r0, r1, r2] = rect
+                .split_vertically(&[Elastic, ratio, Elastic])
+                .try_into()
+                .unwrap();
+            (r1, r0, r2)
+        } else {
+            let [r0, r1, r2] = rect
+                .split_vertically(&[ratio, Elastic, Elastic])
+                .try_into()
+                .unwrap();
+            (r0, r1, r2)
+        };
+        layout_column(api, master, master_rect);
+        layout_column(api, stack0, stack0_rect);
+        layout_column(api, stack1, stack1_rect);
+    }
+
+    fn handle_message(
+        &mut self,
+        _api: &mut ViewHandleMessageApi<'_>,
+        message: &LayoutMessage,
+    ) -> std::ops::ControlFlow<()> {
+        self.params.handle_message(message)
+    }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        if self.master_in_middle {
+            "ThreeColMid".to_string()
+        } else {
+            "ThreeCol".to_string()
+        }
+    }
+}
+
+/// Layout with near-square grid. Columns are filled from left to right, top to bottom.
+#[derive(Clone)]
+pub struct LayoutGrid {}
+
+impl LayoutNodeI for LayoutGrid {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        let mut windows = api.stack().as_vec().clone();
+        let n = windows.len();
+        if n == 0 {
+            return;
+        }
+
+        let ncols = (1..=n).find(|&c| c * c >= n).unwrap();
+        let rects = api
+            .rect()
+            .split_vertically(&vec![SplitSpec::Elastic; ncols]);
+        // Earlier columns have fewer windows, like xmonad's `Grid`.
+        for (i, rect) in rects.into_iter().enumerate() {
+            let nrows = windows.len() / (ncols - i);
+            let column = windows.drain(..nrows).collect();
+            layout_column(api, column, rect);
+        }
+    }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        "Grid".to_string()
+    }
+}
+
+/// Layout that splits the rest area recursively, like xmonad's `Spiral` and `Dwindle`.
+///
+/// Each window takes `ratio` of the rest area, alternating vertical and horizontal splits. With
+/// `spiral`, windows go around clockwise; otherwise they go toward bottom right.
+#[derive(Clone)]
+pub struct LayoutSpiral {
+    ratio: f32,
+    ratio_delta: f32,
+    initial_ratio: f32,
+    spiral: bool,
+}
+
+impl LayoutSpiral {
+    /// - `ratio`: The ratio of the area of each window to the rest area.
+    /// - `ratio_delta`: The amount of ratio changed by `LayoutMessageResize`.
+    /// - `spiral`: Whether to go around clockwise or toward bottom right.
+    pub fn new(ratio: f32, ratio_delta: f32, spiral: bool) -> Self {
+        let ratio = ratio.clamp(0.0, 1.0);
+        Self {
+            ratio,
+            ratio_delta,
+            initial_ratio: ratio,
+            spiral,
+        }
+    }
+}
+
+impl Default for LayoutSpiral {
+    fn default() -> Self {
+        Self::new(0.5, 0.03, true)
+    }
+}
+
+impl LayoutNodeI for LayoutSpiral {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        let windows = api.stack().as_vec().clone();
+        let n = windows.len();
+        let specs = [SplitSpec::Ratio(self.ratio), SplitSpec::Elastic];
+        let specs_rev = [SplitSpec::Elastic, SplitSpec::Ratio(self.ratio)];
+        let mut rect = *api.rect();
+        for (i, window_id) in windows.into_iter().enumerate() {
+            if i == n - 1 {
+                api.layout_window(window_id, rect);
+                break;
+            }
+            // Directions where the window is placed: left, top, right, bottom for spiral.
+            let (window_rect, rest) = match (i % 4, self.spiral) {
+                (0, _) | (2, false) => {
+                    let [r0, r1] = rect.split_vertically_2(specs);
+                    (r0, r1)
+                }
+                (1, _) | (3, false) => {
+                    let [r0, r1] = rect.split_horizontally_2(specs);
+                    (r0, r1)
+                }
+                (2, true) => {
+                    let [r0, r1] = rect.split_vertically_2(specs_rev);
+                    (r1, r0)
                 }
+                (3, true) => {
+                    let [r0, r1] = rect.split_horizontally_2(specs_rev);
+                    (r1, r0)
+                }
+                _ => unreachable!(),
+            };
+            api.layout_window(window_id, window_rect);
+            rect = rest;
+        }
+    }
+
+    fn handle_message(
+        &mut self,
+        _api: &mut ViewHandleMessageApi<'_>,
+        message: &LayoutMessage,
+    ) -> std::ops::ControlFlow<()> {
+        if let Some(message) = message.downcast_ref::<LayoutMessageResize>() {
+            let delta = match message {
+                LayoutMessageResize::Shrink => -self.ratio_delta,
+                LayoutMessageResize::Expand => self.ratio_delta,
+            };
+            self.ratio = (self.ratio + delta).clamp(0.0, 1.0);
+            return std::ops::ControlFlow::Break(());
+        }
+
+        if message.downcast_ref::<LayoutMessageReset>().is_some() {
+            self.ratio = self.initial_ratio;
+            return std::ops::ControlFlow::Break(());
+        }
+
+        std::ops::ControlFlow::Continue(())
+    }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        if self.spiral {
+            "Spiral".to_string()
+        } else {
+            "Dwindle".to_string()
+        }
+    }
+}
+
+/// Layout with an infinite row of columns scrolled horizontally, like PaperWM.
+///
+/// Each window takes `ratio` of the width of the area. The viewport scrolls minimally so that the focused window is
+/// visible. Windows out of the viewport are placed outside of the area.
+#[derive(Clone)]
+pub struct LayoutScrollColumn {
+    ratio: f32,
+    ratio_delta: f32,
+    initial_ratio: f32,
+    // Offset of the viewport from the left edge of the first column. It is updated in `layout()` to follow the focus.
+    offset: Cell<i32>,
+}
+
+impl LayoutScrollColumn {
+    pub fn new(ratio: f32, ratio_delta: f32) -> Self {
+        let ratio = ratio.clamp(0.0, 1.0);
+        Self {
+            ratio,
+            ratio_delta,
+            initial_ratio: ratio,
+            offset: Cell::new(0),
+        }
+    }
+}
+
+impl Default for LayoutScrollColumn {
+    fn default() -> Self {
+        Self::new(0.5, 0.05)
+    }
+}
+
+impl LayoutNodeI for LayoutScrollColumn {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        let stack = api.stack();
+        let windows = stack.as_vec().clone();
+        let focus = stack.focused_index() as i32;
+        let n = windows.len() as i32;
+
+        let rect = *api.rect();
+        let width = ((rect.size.w as f32 * self.ratio).round() as i32).max(1);
+        let mut offset = self.offset.get();
+        // Scroll to the focused column, and don't leave empty space at the right end.
+        offset = offset.min(focus * width);
+        offset = offset.max((focus + 1) * width - rect.size.w);
+        offset = offset.min(n * width - rect.size.w).max(0);
+        self.offset.set(offset);
+
+        for (i, window_id) in windows.into_iter().enumerate() {
+            let x = rect.loc.x + i as i32 * width - offset;
+            let geometry = Rectangle::new((x, rect.loc.y).into(), (width, rect.size.h).into());
+            api.layout_window(window_id, geometry);
+        }
+    }
+
+    fn handle_message(
+        &mut self,
+        _api: &mut ViewHandleMessageApi<'_>,
+        message: &LayoutMessage,
+    ) -> std::ops::ControlFlow<()> {
+        if let Some(message) = message.downcast_ref::<LayoutMessageResize>() {
+            let delta = match message {
+                LayoutMessageResize::Shrink => -self.ratio_delta,
+                LayoutMessageResize::Expand => self.ratio_delta,
+            };
+            self.ratio = (self.ratio + delta).clamp(0.0, 1.0);
+            return std::ops::ControlFlow::Break(());
+        }
+
+        if message.downcast_ref::<LayoutMessageReset>().is_some() {
+            self.ratio = self.initial_ratio;
+            self.offset.set(0);
+            return std::ops::ControlFlow::Break(());
+        }
+
+        std::ops::ControlFlow::Continue(())
+    }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        "ScrollColumn".to_string()
+    }
+}
+
+/// Layout that shows the focused window with a tab bar, like i3's tabbed layout.
+#[derive(Clone)]
+pub struct LayoutTabbed {
+    bar_height: u32,
+    active_rgba: Rgba,
+    inactive_rgba: Rgba,
+    text_rgba: Rgba,
+}
+
+impl LayoutTabbed {
+    pub fn new(bar_height: u32, active_rgba: Rgba, inactive_rgba: Rgba, text_rgba: Rgba) -> Self {
+        Self {
+            bar_height,
+            active_rgba,
+            inactive_rgba,
+            text_rgba,
+        }
+    }
+}
+
+impl Default for LayoutTabbed {
+    fn default() -> Self {
+        Self::new(
+            20,
+            Rgba::from_rgba(0x556b2fff),
+            Rgba::from_rgba(0x333333ff),
+            Rgba::from_rgba(0xffffffff),
+        )
+    }
+}
+
+impl LayoutTabbed {
+    /// Lays out a tab bar at the top of `rect` and returns the rest area. Each tab shows the title of the window.
+    fn layout_tab_bar(
+        &self,
+        api: &mut ViewLayoutApi<'_>,
+        rect: Rectangle<i32, Logical>,
+        tabs: Vec<Id<Window>>,
+        active: usize,
+    ) -> Rectangle<i32, Logical> {
+        let [bar_rect, rest] = rect.split_horizontally_2([
+            SplitSpec::FixedSize(self.bar_height as usize),
+            SplitSpec::Elastic,
+        ]);
+
+        let tab_rects = bar_rect.split_vertically(&vec![SplitSpec::Elastic; tabs.len()]);
+        let n = tabs.len();
+        for (i, (window_id, mut rect)) in izip!(tabs, tab_rects).enumerate() {
+            // Separator
+            if i != n - 1 {
+                rect.size.w = 0.max(rect.size.w - 1);
             }
+            let background_rgba = if i == active {
+                self.active_rgba.clone()
+            } else {
+                self.inactive_rgba.clone()
+            };
+            api.add_decoration(Decoration {
+                rect,
+                background_rgba,
+                label: Some(DecorationLabel::WindowTitle(window_id)),
+                label_rgba: self.text_rgba.clone(),
+                focus_on_click: Some(window_id),
+            });
         }
+
+        rest
+    }
+}
+
+impl LayoutNodeI for LayoutTabbed {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        let stack = api.stack();
+        let Some(&focus) = stack.focus() else {
+            return;
+        };
+        let windows = stack.as_vec().clone();
+        let active = stack.focused_index();
+
+        let rect = *api.rect();
+        let window_rect = self.layout_tab_bar(api, rect, windows, active);
+        api.layout_window(focus, window_rect);
+    }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        "Tabbed".to_string()
+    }
+}
+
+/// Changes the container tree of `LayoutManual` around the focused window.
+#[derive(Debug, Clone)]
+pub enum LayoutMessageManual {
+    /// Splits the container of the focused window, like i3's `split`.
+    Split(ContainerKind),
+    /// Changes the kind of the container of the focused window, like i3's `layout`.
+    SetKind(ContainerKind),
+    /// Moves the focused window, like i3's `move`.
+    Move(MoveDirection),
+}
+
+impl LayoutMessageI for LayoutMessageManual {}
+
+/// Manual tiling layout with a tree of containers, like i3.
+///
+/// New windows are inserted next to the focused one, and removed windows are removed from the tree on the next layout.
+/// Tabbed containers are drawn with the style of `tabbed`.
+#[derive(Clone)]
+pub struct LayoutManual {
+    tree: RefCell<SplitTree<Id<Window>>>,
+    // Focused window at the last layout. New windows are inserted next to it.
+    last_focus: Cell<Option<Id<Window>>>,
+    tabbed: LayoutTabbed,
+}
+
+impl LayoutManual {
+    pub fn new(kind: ContainerKind, tabbed: LayoutTabbed) -> Self {
+        Self {
+            tree: RefCell::new(SplitTree::new(kind)),
+            last_focus: Cell::new(None),
+            tabbed,
+        }
+    }
+
+    /// Makes the tree have the same windows as the stack.
+    fn reconcile(&self, stack: &FocusedVec<Id<Window>>) {
+        let mut tree = self.tree.borrow_mut();
+        tree.retain(|window_id| stack.as_vec().contains(&window_id));
+        let mut target = self.last_focus.get().filter(|&x| tree.contains(x));
+        for &window_id in stack.as_vec() {
+            if !tree.contains(window_id) {
+                tree.insert_after(target, window_id);
+                target = Some(window_id);
+            }
+        }
+        self.last_focus.set(stack.focus().copied());
+    }
+
+    fn layout_container(
+        &self,
+        api: &mut ViewLayoutApi<'_>,
+        container: &Container<Id<Window>>,
+        rect: Rectangle<i32, Logical>,
+        focus: Option<Id<Window>>,
+    ) {
+        let n = container.children.len();
+        if n == 0 {
+            return;
+        }
+
+        let specs = vec![SplitSpec::Elastic; n];
+        match container.kind {
+            ContainerKind::Horizontal => {
+                for (child, rect) in izip!(&container.children, rect.split_vertically(&specs)) {
+                    self.layout_child(api, child, rect, focus);
+                }
+            }
+            ContainerKind::Vertical => {
+                for (child, rect) in izip!(&container.children, rect.split_horizontally(&specs)) {
+                    self.layout_child(api, child, rect, focus);
+                }
+            }
+            ContainerKind::Tabbed => {
+                // Show the child that has the focused window.
+                let active = container
+                    .children
+                    .iter()
+                    .position(|child| focus.is_some_and(|focus| child.contains(focus)))
+                    .unwrap_or(0);
+                let tabs = container
+                    .children
+                    .iter()
+                    .map(|child| child.first_leaf().unwrap())
+                    .collect();
+                let rect = self.tabbed.layout_tab_bar(api, rect, tabs, active);
+                self.layout_child(api, &container.children[active], rect, focus);
+            }
+        }
+    }
+
+    fn layout_child(
+        &self,
+        api: &mut ViewLayoutApi<'_>,
+        child: &SplitTreeNode<Id<Window>>,
+        rect: Rectangle<i32, Logical>,
+        focus: Option<Id<Window>>,
+    ) {
+        match child {
+            SplitTreeNode::Leaf(window_id) => api.layout_window(*window_id, rect),
+            SplitTreeNode::Container(container) => {
+                self.layout_container(api, container, rect, focus)
+            }
+        }
+    }
+}
+
+impl Default for LayoutManual {
+    fn default() -> Self {
+        Self::new(ContainerKind::Horizontal, LayoutTabbed::default())
+    }
+}
+
+impl LayoutNodeI for LayoutManual {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        let stack = api.stack().clone();
+        self.reconcile(&stack);
+
+        let tree = self.tree.borrow();
+        let rect = *api.rect();
+        self.layout_container(api, tree.root(), rect, stack.focus().copied());
+    }
+
+    fn handle_message(
+        &mut self,
+        api: &mut ViewHandleMessageApi<'_>,
+        message: &LayoutMessage,
+    ) -> std::ops::ControlFlow<()> {
+        let Some(message) = message.downcast_ref::<LayoutMessageManual>() else {
+            return std::ops::ControlFlow::Continue(());
+        };
+        let Some(&focus) = api.stack().focus() else {
+            return std::ops::ControlFlow::Break(());
+        };
+
+        let tree = self.tree.get_mut();
+        match message {
+            LayoutMessageManual::Split(kind) => tree.split(focus, *kind),
+            LayoutMessageManual::SetKind(kind) => tree.set_kind(focus, *kind),
+            LayoutMessageManual::Move(direction) => tree.move_leaf(focus, *direction),
+        }
+
+        std::ops::ControlFlow::Break(())
+    }
+
+    fn description(&self, _api: &ViewDescribeApi<'_>) -> String {
+        "Manual".to_string()
     }
 }
 
@@ -85,6 +750,10 @@ impl LayoutNodeI for LayoutNodeSelect {
 
         std::ops::ControlFlow::Break(())
     }
+
+    fn description(&self, api: &ViewDescribeApi<'_>) -> String {
+        api.describe_node(*self.node_ids.focus())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -124,28 +793,173 @@ impl LayoutNodeI for LayoutNodeToggle {
 
         std::ops::ControlFlow::Break(())
     }
+
+    fn description(&self, api: &ViewDescribeApi<'_>) -> String {
+        api.describe_node(*self.node_ids.focus())
+    }
+}
+
+/// How `LayoutNodeCombine` partitions windows between two children.
+#[derive(Clone)]
+pub enum CombinePartition {
+    /// The first N windows in the stack go to the first child.
+    FirstN(usize),
+    /// Windows matching the predicate go to the first child.
+    Predicate(Arc<dyn Fn(&Window) -> bool>),
+}
+
+/// Splits the area into left and right, and lays out a part of windows with each child, like xmonad's `combineTwo`.
+///
+/// The area is split by `ratio`. If one of the parts has no windows, the other child takes the whole area. Wrap it
+/// with `LayoutNodeTransform` for top and bottom. Messages are sent to both children.
+#[derive(Clone)]
+pub struct LayoutNodeCombine {
+    first: Id<LayoutNode>,
+    second: Id<LayoutNode>,
+    partition: CombinePartition,
+    ratio: f32,
+}
+
+impl LayoutNodeCombine {
+    pub fn new(
+        first: Id<LayoutNode>,
+        second: Id<LayoutNode>,
+        partition: CombinePartition,
+        ratio: f32,
+    ) -> Self {
+        Self {
+            first,
+            second,
+            partition,
+            ratio: ratio.clamp(0.0, 1.0),
+        }
+    }
+
+    fn is_first(&self, api: &ViewLayoutApi<'_>, i: usize, window_id: Id<Window>) -> bool {
+        match &self.partition {
+            CombinePartition::FirstN(n) => i < *n,
+            CombinePartition::Predicate(f) => api.window(window_id).is_some_and(|w| f(w)),
+        }
+    }
+}
+
+impl LayoutNodeI for LayoutNodeCombine {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        let stack = api.stack();
+        let focus = stack.focus().copied();
+        let (first, second): (Vec<_>, Vec<_>) = stack
+            .as_vec()
+            .iter()
+            .enumerate()
+            .partition(|&(i, &window_id)| self.is_first(api, i, window_id));
+        // Keep the focus if the window is in the part. Otherwise, focus the first one.
+        let to_stack = |part: Vec<(usize, &Id<Window>)>| {
+            let vec = part
+                .into_iter()
+                .map(|(_, &window_id)| window_id)
+                .collect::<Vec<_>>();
+            let focus = vec.iter().position(|&x| Some(x) == focus).unwrap_or(0);
+            FocusedVec::new(vec, focus)
+        };
+        let first = to_stack(first);
+        let second = to_stack(second);
+
+        let rect = *api.rect();
+        match (first.is_empty(), second.is_empty()) {
+            (_, true) => api.layout_node_with_stack(self.first, rect, &first),
+            (true, false) => api.layout_node_with_stack(self.second, rect, &second),
+            (false, false) => {
+                let [r0, r1] =
+                    rect.split_vertically_2([SplitSpec::Ratio(self.ratio), SplitSpec::Elastic]);
+                api.layout_node_with_stack(self.first, r0, &first);
+                api.layout_node_with_stack(self.second, r1, &second);
+            }
+        }
+    }
+
+    fn handle_message(
+        &mut self,
+        api: &mut ViewHandleMessageApi<'_>,
+        message: &LayoutMessage,
+    ) -> std::ops::ControlFlow<()> {
+        let r0 = api.handle_message(self.first, message);
+        let r1 = api.handle_message(self.second, message);
+        if r0.is_break() || r1.is_break() {
+            std::ops::ControlFlow::Break(())
+        } else {
+            std::ops::ControlFlow::Continue(())
+        }
+    }
+
+    fn description(&self, api: &ViewDescribeApi<'_>) -> String {
+        format!(
+            "Combine {} {}",
+            api.describe_node(self.first),
+            api.describe_node(self.second)
+        )
+    }
+}
+
+/// Returns the number of windows laid out in `queue` that are visible in `rect`, for smart margins and borders.
+fn count_visible_windows(
+    queue: &[(Id<Window>, WindowProps)],
+    rect: &Rectangle<i32, Logical>,
+) -> usize {
+    queue
+        .iter()
+        .filter(|(_, props)| rect.overlaps(props.geometry) || rect.contains_rect(props.geometry))
+        .count()
 }
 
 #[derive(Debug, Clone)]
 pub struct LayoutNodeMargin {
     child: Id<LayoutNode>,
     margin: Thickness,
+    smart: bool,
 }
 
 impl LayoutNodeMargin {
     pub fn new(child: Id<LayoutNode>, margin: Thickness) -> Self {
-        Self { child, margin }
+        Self {
+            child,
+            margin,
+            smart: false,
+        }
+    }
+
+    /// Same as `new()`, but the margin is dropped if the child lays out at most one window visible in the area,
+    /// e.g. by `LayoutFull`, like xmonad's `smartSpacing`.
+    pub fn new_smart(child: Id<LayoutNode>, margin: Thickness) -> Self {
+        Self {
+            child,
+            margin,
+            smart: true,
+        }
     }
 }
 
 impl LayoutNodeI for LayoutNodeMargin {
     fn layout(&self, api: &mut ViewLayoutApi<'_>) {
-        api.layout_node(self.child, *api.rect());
-        api.modify_layout_queue_with(|queue| {
+        let rect = *api.rect();
+        api.layout_node(self.child, rect);
+        let mut skip = false;
+        api.modify_own_layout_queue_with(|queue| {
+            skip = self.smart && count_visible_windows(queue, &rect) <= 1;
+            if skip {
+                return;
+            }
             for (_, props) in queue {
                 props.geometry = props.geometry.shrink(self.margin.clone());
             }
         });
+        if skip {
+            return;
+        }
+        api.modify_decoration_queue_with(|queue| {
+            for decoration in queue {
+                decoration.rect = decoration.rect.shrink(self.margin.clone());
+            }
+        });
     }
 
     fn handle_message(
@@ -155,29 +969,162 @@ impl LayoutNodeI for LayoutNodeMargin {
     ) -> std::ops::ControlFlow<()> {
         api.handle_message(self.child, message)
     }
+
+    fn description(&self, api: &ViewDescribeApi<'_>) -> String {
+        api.describe_node(self.child)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct LayoutNodeBorder {
     child: Id<LayoutNode>,
     border: Border,
+    smart: bool,
 }
 
 impl LayoutNodeBorder {
     pub fn new(child: Id<LayoutNode>, border: Border) -> Self {
-        Self { child, border }
+        Self {
+            child,
+            border,
+            smart: false,
+        }
+    }
+
+    /// Same as `new()`, but the border is dropped if the child lays out at most one window visible in the area,
+    /// e.g. by `LayoutFull`, like xmonad's `smartBorders`.
+    pub fn new_smart(child: Id<LayoutNode>, border: Border) -> Self {
+        Self {
+            child,
+            border,
+            smart: true,
+        }
     }
 }
 
 impl LayoutNodeI for LayoutNodeBorder {
     fn layout(&self, api: &mut ViewLayoutApi<'_>) {
-        api.layout_node(self.child, *api.rect());
-        api.modify_layout_queue_with(|queue| {
+        let rect = *api.rect();
+        api.layout_node(self.child, rect);
+        let mut skip = false;
+        api.modify_own_layout_queue_with(|queue| {
+            skip = self.smart && count_visible_windows(queue, &rect) <= 1;
+            if skip {
+                return;
+            }
             for (_, props) in queue {
                 props.geometry = props.geometry.shrink(self.border.dim.clone());
                 props.border = self.border.clone();
             }
         });
+        if skip {
+            return;
+        }
+        api.modify_decoration_queue_with(|queue| {
+            for decoration in queue {
+                decoration.rect = decoration.rect.shrink(self.border.dim.clone());
+            }
+        });
+    }
+
+    fn handle_message(
+        &mut self,
+        api: &mut ViewHandleMessageApi<'_>,
+        message: &LayoutMessage,
+    ) -> std::ops::ControlFlow<()> {
+        api.handle_message(self.child, message)
+    }
+
+    fn description(&self, api: &ViewDescribeApi<'_>) -> String {
+        api.describe_node(self.child)
+    }
+}
+
+/// Transformation applied by `LayoutNodeTransform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutTransform {
+    /// Swaps x and y axes, e.g. turns `LayoutTall` into a "wide" layout. (xmonad's `Mirror`)
+    Mirror,
+    /// Flips left and right. (xmonad's `reflectHoriz`)
+    ReflectHorizontally,
+    /// Flips top and bottom. (xmonad's `reflectVert`)
+    ReflectVertically,
+    /// Rotates 90 degrees clockwise.
+    Rotate,
+}
+
+/// Toggles `LayoutNodeTransform` with the given transformation.
+#[derive(Debug, Clone)]
+pub struct LayoutMessageToggleTransform(pub LayoutTransform);
+
+impl LayoutMessageI for LayoutMessageToggleTransform {}
+
+/// Transforms geometries of windows and decorations laid out by the child.
+///
+/// `Mirror` and `Rotate` scale geometries to fit in the original rectangle, so this should be placed
+/// inside `LayoutNodeMargin` and `LayoutNodeBorder`.
+#[derive(Debug, Clone)]
+pub struct LayoutNodeTransform {
+    child: Id<LayoutNode>,
+    transform: LayoutTransform,
+    enabled: bool,
+}
+
+impl LayoutNodeTransform {
+    pub fn new(child: Id<LayoutNode>, transform: LayoutTransform) -> Self {
+        Self {
+            child,
+            transform,
+            enabled: true,
+        }
+    }
+
+    /// Transforms `g` in `rect`.
+    fn transform_rect(
+        &self,
+        rect: &Rectangle<i32, Logical>,
+        g: Rectangle<i32, Logical>,
+    ) -> Rectangle<i32, Logical> {
+        let xr = rect.loc.x..(rect.loc.x + rect.size.w);
+        let yr = rect.loc.y..(rect.loc.y + rect.size.h);
+        let flip_x = |x: i32| xr.start + xr.end - x;
+        let flip_y = |y: i32| yr.start + yr.end - y;
+        let (left, right) = (g.loc.x, g.loc.x + g.size.w);
+        let (top, bottom) = (g.loc.y, g.loc.y + g.size.h);
+        let (new_xr, new_yr) = match self.transform {
+            LayoutTransform::Mirror => (
+                rescale(top, &yr, &xr)..rescale(bottom, &yr, &xr),
+                rescale(left, &xr, &yr)..rescale(right, &xr, &yr),
+            ),
+            LayoutTransform::ReflectHorizontally => (flip_x(right)..flip_x(left), top..bottom),
+            LayoutTransform::ReflectVertically => (left..right, flip_y(bottom)..flip_y(top)),
+            LayoutTransform::Rotate => (
+                flip_x(rescale(bottom, &yr, &xr))..flip_x(rescale(top, &yr, &xr)),
+                rescale(left, &xr, &yr)..rescale(right, &xr, &yr),
+            ),
+        };
+        Rectangle::from_ranges(new_xr, new_yr)
+    }
+}
+
+impl LayoutNodeI for LayoutNodeTransform {
+    fn layout(&self, api: &mut ViewLayoutApi<'_>) {
+        api.layout_node(self.child, *api.rect());
+        if !self.enabled {
+            return;
+        }
+
+        let rect = *api.rect();
+        api.modify_own_layout_queue_with(|queue| {
+            for (_, props) in queue {
+                props.geometry = self.transform_rect(&rect, props.geometry);
+            }
+        });
+        api.modify_decoration_queue_with(|queue| {
+            for decoration in queue {
+                decoration.rect = self.transform_rect(&rect, decoration.rect);
+            }
+        });
     }
 
     fn handle_message(
@@ -185,6 +1132,511 @@ impl LayoutNodeI for LayoutNodeBorder {
         api: &mut ViewHandleMessageApi<'_>,
         message: &LayoutMessage,
     ) -> std::ops::ControlFlow<()> {
+        if let Some(LayoutMessageToggleTransform(transform)) =
+            message.downcast_ref::<LayoutMessageToggleTransform>()
+        {
+            if *transform == self.transform {
+                self.enabled = !self.enabled;
+                return std::ops::ControlFlow::Break(());
+            }
+        }
+
         api.handle_message(self.child, message)
     }
+
+    fn description(&self, api: &ViewDescribeApi<'_>) -> String {
+        let child = api.describe_node(self.child);
+        if !self.enabled {
+            return child;
+        }
+        let prefix = match self.transform {
+            LayoutTransform::Mirror => "Mirror",
+            LayoutTransform::ReflectHorizontally => "ReflectX",
+            LayoutTransform::ReflectVertically => "ReflectY",
+            LayoutTransform::Rotate => "Rotate",
+        };
+        format!("{prefix} {child}")
+    }
+}
+
+/// Maps `v` in `from` to `to` linearly.
+fn rescale(v: i32, from: &Range<i32>, to: &Range<i32>) -> i32 {
+    let from_len = (from.end - from.start) as i64;
+    let to_len = (to.end - to.start) as i64;
+    if from_len <= 0 {
+        return to.start;
+    }
+    // Round to the nearest so that adjacent windows keep adjacent.
+    let d = ((v - from.start) as i64 * to_len * 2 + from_len).div_euclid(from_len * 2);
+    to.start + d as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::layout_node::LayoutTreeBuilder;
+    use crate::view::stackset::{StackSet, WorkspaceTag};
+    use std::collections::HashMap;
+
+    /// Makes a stackset with `n` windows and the tree whose root is the last node.
+    fn stackset(nodes: Vec<LayoutNode>, n: u64) -> StackSet {
+        let root_id = nodes.last().unwrap().id();
+        let nodes = nodes.into_iter().map(|node| (node.id(), node)).collect();
+        let builder = LayoutTreeBuilder::new(nodes, root_id);
+        let mut stackset = StackSet::new(vec![(WorkspaceTag("1".to_string()), builder)]);
+        for i in 0..n {
+            stackset.workspaces.focus_mut().stack.push(Id::from(i));
+        }
+        stackset
+    }
+
+    /// Lays out windows of the focused workspace in a 100x100 rect.
+    fn layout_stackset(stackset: &StackSet) -> (Vec<(Id<Window>, WindowProps)>, Vec<Decoration>) {
+        let workspace = stackset.workspaces().focus();
+        let windows = HashMap::new();
+        let mut layout_queue = vec![];
+        let mut decoration_queue = vec![];
+        let mut api = ViewLayoutApi {
+            workspace,
+            windows: &windows,
+            stack: &workspace.stack,
+            // Safety: `LayoutTree` is not borrowed elsewhere.
+            layout_tree: unsafe { workspace.borrow_layout_tree() },
+            layout_queue: &mut layout_queue,
+            decoration_queue: &mut decoration_queue,
+            rect: rect(0, 0, 100, 100),
+            layout_queue_start: 0,
+            decoration_queue_start: 0,
+        };
+        api.layout_node_root();
+        (layout_queue, decoration_queue)
+    }
+
+    /// Lays out `n` windows in a 100x100 rect with the tree whose root is the last node.
+    fn layout(nodes: Vec<LayoutNode>, n: u64) -> (Vec<(Id<Window>, WindowProps)>, Vec<Decoration>) {
+        layout_stackset(&stackset(nodes, n))
+    }
+
+    /// Sends a message to the root of the focused workspace's layout tree.
+    fn send_message(stackset: &StackSet, message: impl Into<LayoutMessage>) {
+        let workspace = stackset.workspaces().focus();
+        let mut api = ViewHandleMessageApi {
+            stack: &workspace.stack,
+            // Safety: `LayoutTree` is not borrowed elsewhere.
+            layout_tree: unsafe { workspace.borrow_layout_tree() },
+        };
+        api.handle_message_root(&message.into());
+    }
+
+    fn geometries(queue: &[(Id<Window>, WindowProps)]) -> Vec<Rectangle<i32, Logical>> {
+        queue.iter().map(|(_, props)| props.geometry).collect()
+    }
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::new((x, y).into(), (w, h).into())
+    }
+
+    #[test]
+    fn test_combine_with_border_in_child() {
+        let border = Border {
+            dim: 2.into(),
+            active_rgba: Rgba::from_rgba(0xffffffff),
+            inactive_rgba: Rgba::from_rgba(0x000000ff),
+        };
+        let tall = LayoutNode::from(LayoutTall::default());
+        let grid = LayoutNode::from(LayoutGrid {});
+        let border = LayoutNode::from(LayoutNodeBorder::new(grid.id(), border));
+        let combine = LayoutNode::from(LayoutNodeCombine::new(
+            tall.id(),
+            border.id(),
+            CombinePartition::FirstN(1),
+            0.5,
+        ));
+        let (queue, _) = layout(vec![tall, grid, border, combine], 3);
+
+        assert_eq!(queue.len(), 3);
+        // The window laid out by the first child is not bordered.
+        assert_eq!(queue[0].1.geometry, rect(0, 0, 50, 100));
+        assert_eq!(queue[0].1.border.dim, Thickness::from(0));
+        for (_, props) in &queue[1..] {
+            assert!(rect(50, 0, 50, 100).contains_rect(props.geometry));
+            assert_eq!(props.border.dim, Thickness::from(2));
+        }
+    }
+
+    #[test]
+    fn test_combine_with_transform_in_child() {
+        let tall = LayoutNode::from(LayoutTall::default());
+        let full = LayoutNode::from(LayoutFull {});
+        let mirror = LayoutNode::from(LayoutNodeTransform::new(full.id(), LayoutTransform::Mirror));
+        let combine = LayoutNode::from(LayoutNodeCombine::new(
+            tall.id(),
+            mirror.id(),
+            CombinePartition::FirstN(1),
+            0.5,
+        ));
+        let (queue, _) = layout(vec![tall, full, mirror, combine], 2);
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].1.geometry, rect(0, 0, 50, 100));
+        assert_eq!(queue[1].1.geometry, rect(50, 0, 50, 100));
+    }
+
+    #[test]
+    fn test_smart_border_counts_visible_windows() {
+        let border = Border {
+            dim: 2.into(),
+            active_rgba: Rgba::from_rgba(0xffffffff),
+            inactive_rgba: Rgba::from_rgba(0x000000ff),
+        };
+
+        // Only the focused column is visible.
+        let scroll = LayoutNode::from(LayoutScrollColumn::new(1.0, 0.05));
+        let smart = LayoutNode::from(LayoutNodeBorder::new_smart(scroll.id(), border.clone()));
+        let (queue, _) = layout(vec![scroll, smart], 2);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].1.geometry, rect(0, 0, 100, 100));
+        assert_eq!(queue[0].1.border.dim, Thickness::from(0));
+
+        let scroll = LayoutNode::from(LayoutScrollColumn::new(0.5, 0.05));
+        let smart = LayoutNode::from(LayoutNodeBorder::new_smart(scroll.id(), border));
+        let (queue, _) = layout(vec![scroll, smart], 2);
+        assert_eq!(queue[0].1.geometry, rect(2, 2, 46, 96));
+        assert_eq!(queue[0].1.border.dim, Thickness::from(2));
+    }
+
+    #[test]
+    fn test_transform_moves_decorations() {
+        let tabbed = LayoutNode::from(LayoutTabbed::default());
+        let reflect = LayoutNode::from(LayoutNodeTransform::new(
+            tabbed.id(),
+            LayoutTransform::ReflectVertically,
+        ));
+        let (queue, decorations) = layout(vec![tabbed, reflect], 2);
+
+        // The tab bar follows the window to the bottom.
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].1.geometry, rect(0, 0, 100, 80));
+        assert_eq!(decorations.len(), 2);
+        for decoration in &decorations {
+            assert_eq!(decoration.rect.loc.y, 80);
+            assert_eq!(decoration.rect.size.h, 20);
+        }
+    }
+
+    #[test]
+    fn test_tall_inc_master_is_not_clamped() {
+        let tall = LayoutNode::from(LayoutTall::new(1, 0.5, 0.25));
+        let mut stackset = stackset(vec![tall], 1);
+
+        // The master count may exceed the number of windows, as in xmonad.
+        send_message(&stackset, LayoutMessageIncMaster(1));
+        send_message(&stackset, LayoutMessageIncMaster(1));
+        let (queue, _) = layout_stackset(&stackset);
+        assert_eq!(geometries(&queue), vec![rect(0, 0, 100, 100)]);
+
+        // So new windows go to the master area until it is filled.
+        for i in 1..4 {
+            stackset.workspaces.focus_mut().stack.push(Id::from(i));
+        }
+        let (queue, _) = layout_stackset(&stackset);
+        let gs = geometries(&queue);
+        assert_eq!(gs.len(), 4);
+        assert!(gs[..3].iter().all(|g| g.loc.x == 0 && g.size.w == 50));
+        assert_eq!(gs[3], rect(50, 0, 50, 100));
+
+        send_message(&stackset, LayoutMessageIncMaster(-2));
+        let (queue, _) = layout_stackset(&stackset);
+        let gs = geometries(&queue);
+        assert_eq!(gs[0], rect(0, 0, 50, 100));
+        assert!(gs[1..].iter().all(|g| g.loc.x == 50 && g.size.w == 50));
+
+        // No window is in the master area.
+        send_message(&stackset, LayoutMessageIncMaster(-5));
+        let (queue, _) = layout_stackset(&stackset);
+        assert!(geometries(&queue)
+            .iter()
+            .all(|g| g.loc.x == 0 && g.size.w == 100));
+    }
+
+    #[test]
+    fn test_tall_resize_and_reset() {
+        let tall = LayoutNode::from(LayoutTall::new(1, 0.5, 0.25));
+        let stackset = stackset(vec![tall], 2);
+
+        send_message(&stackset, LayoutMessageResize::Shrink);
+        let (queue, _) = layout_stackset(&stackset);
+        assert_eq!(
+            geometries(&queue),
+            vec![rect(0, 0, 25, 100), rect(25, 0, 75, 100)]
+        );
+
+        send_message(&stackset, LayoutMessageResize::Expand);
+        send_message(&stackset, LayoutMessageResize::Expand);
+        let (queue, _) = layout_stackset(&stackset);
+        assert_eq!(
+            geometries(&queue),
+            vec![rect(0, 0, 75, 100), rect(75, 0, 25, 100)]
+        );
+
+        // The ratio doesn't exceed 1.
+        for _ in 0..3 {
+            send_message(&stackset, LayoutMessageResize::Expand);
+        }
+        send_message(&stackset, LayoutMessageResize::Shrink);
+        let (queue, _) = layout_stackset(&stackset);
+        assert_eq!(
+            geometries(&queue),
+            vec![rect(0, 0, 75, 100), rect(75, 0, 25, 100)]
+        );
+
+        send_message(&stackset, LayoutMessageIncMaster(1));
+        let (queue, _) = layout_stackset(&stackset);
+        assert_eq!(
+            geometries(&queue),
+            vec![rect(0, 0, 100, 50), rect(0, 50, 100, 50)]
+        );
+
+        send_message(&stackset, LayoutMessageReset);
+        let (queue, _) = layout_stackset(&stackset);
+        assert_eq!(
+            geometries(&queue),
+            vec![rect(0, 0, 50, 100), rect(50, 0, 50, 100)]
+        );
+    }
+
+    #[test]
+    fn test_transform_rect() {
+        let transform = |t| LayoutNodeTransform::new(Id::from(0), t);
+        let r = rect(10, 20, 100, 50);
+        let g = rect(10, 20, 40, 30);
+
+        assert_eq!(
+            transform(LayoutTransform::Mirror).transform_rect(&r, g),
+            rect(10, 20, 60, 20)
+        );
+        assert_eq!(
+            transform(LayoutTransform::ReflectHorizontally).transform_rect(&r, g),
+            rect(70, 20, 40, 30)
+        );
+        assert_eq!(
+            transform(LayoutTransform::ReflectVertically).transform_rect(&r, g),
+            rect(10, 40, 40, 30)
+        );
+        assert_eq!(
+            transform(LayoutTransform::Rotate).transform_rect(&r, g),
+            rect(50, 20, 60, 20)
+        );
+
+        // The whole rect is kept.
+        for t in [
+            LayoutTransform::Mirror,
+            LayoutTransform::ReflectHorizontally,
+            LayoutTransform::ReflectVertically,
+            LayoutTransform::Rotate,
+        ] {
+            assert_eq!(transform(t).transform_rect(&r, r), r);
+        }
+    }
+
+    #[test]
+    fn test_transform_rect_round_trip() {
+        let r = rect(3, 5, 99, 99);
+        let mut gs = r.split_vertically(&[SplitSpec::Elastic; 2]);
+        let rows = gs.remove(0).split_horizontally(&[SplitSpec::Elastic; 3]);
+        gs.extend(rows);
+
+        for (t, n) in [
+            (LayoutTransform::Mirror, 2),
+            (LayoutTransform::ReflectHorizontally, 2),
+            (LayoutTransform::ReflectVertically, 2),
+            (LayoutTransform::Rotate, 4),
+        ] {
+            let transform = LayoutNodeTransform::new(Id::from(0), t);
+            for &g in &gs {
+                let h = (0..n).fold(g, |h, _| transform.transform_rect(&r, h));
+                assert_eq!(h, g, "{t:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_rect_keeps_tiling() {
+        let r = rect(3, 5, 101, 51);
+        let mut gs = r.split_vertically(&[SplitSpec::Elastic; 3]);
+        let rows = gs.remove(0).split_horizontally(&[SplitSpec::Elastic; 3]);
+        gs.extend(rows);
+        let area = |g: &Rectangle<i32, Logical>| g.size.w * g.size.h;
+        let overlaps = |a: &Rectangle<i32, Logical>, b: &Rectangle<i32, Logical>| {
+            a.loc.x < b.loc.x + b.size.w
+                && b.loc.x < a.loc.x + a.size.w
+                && a.loc.y < b.loc.y + b.size.h
+                && b.loc.y < a.loc.y + a.size.h
+        };
+
+        // Transformed geometries have no gaps nor overlaps in the plain rect.
+        for t in [
+            LayoutTransform::Mirror,
+            LayoutTransform::ReflectHorizontally,
+            LayoutTransform::ReflectVertically,
+            LayoutTransform::Rotate,
+        ] {
+            let transform = LayoutNodeTransform::new(Id::from(0), t);
+            let hs = gs
+                .iter()
+                .map(|&g| transform.transform_rect(&r, g))
+                .collect::<Vec<_>>();
+            assert!(hs.iter().all(|&h| r.contains_rect(h)), "{t:?}");
+            assert_eq!(hs.iter().map(area).sum::<i32>(), area(&r), "{t:?}");
+            for (i, a) in hs.iter().enumerate() {
+                assert!(hs[i + 1..].iter().all(|b| !overlaps(a, b)), "{t:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_three_column() {
+        let geometries_of = |master_in_middle: bool, ratio: f32, n: u64| {
+            let three = LayoutNode::from(LayoutThreeColumn::new(1, ratio, 0.1, master_in_middle));
+            geometries(&layout(vec![three], n).0)
+        };
+
+        for master_in_middle in [true, false] {
+            assert_eq!(geometries_of(master_in_middle, 0.5, 0), vec![]);
+            assert_eq!(
+                geometries_of(master_in_middle, 0.5, 1),
+                vec![rect(0, 0, 100, 100)]
+            );
+            // Same as `LayoutTall`.
+            assert_eq!(
+                geometries_of(master_in_middle, 0.5, 2),
+                vec![rect(0, 0, 50, 100), rect(50, 0, 50, 100)]
+            );
+        }
+
+        assert_eq!(
+            geometries_of(true, 0.5, 3),
+            vec![
+                rect(25, 0, 50, 100),
+                rect(0, 0, 25, 100),
+                rect(75, 0, 25, 100)
+            ]
+        );
+        assert_eq!(
+            geometries_of(false, 0.5, 3),
+            vec![
+                rect(0, 0, 50, 100),
+                rect(50, 0, 25, 100),
+                rect(75, 0, 25, 100)
+            ]
+        );
+
+        // The first stack column takes more windows and remainders go to earlier ones.
+        assert_eq!(
+            geometries_of(true, 0.45, 6),
+            vec![
+                rect(28, 0, 45, 100),
+                rect(0, 0, 28, 34),
+                rect(0, 34, 28, 33),
+                rect(0, 67, 28, 33),
+                rect(73, 0, 27, 50),
+                rect(73, 50, 27, 50),
+            ]
+        );
+        assert_eq!(
+            geometries_of(false, 0.45, 6),
+            vec![
+                rect(0, 0, 45, 100),
+                rect(45, 0, 28, 34),
+                rect(45, 34, 28, 33),
+                rect(45, 67, 28, 33),
+                rect(73, 0, 27, 50),
+                rect(73, 50, 27, 50),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid() {
+        let geometries_of =
+            |n: u64| geometries(&layout(vec![LayoutNode::from(LayoutGrid {})], n).0);
+
+        assert_eq!(geometries_of(0), vec![]);
+        assert_eq!(geometries_of(1), vec![rect(0, 0, 100, 100)]);
+        assert_eq!(
+            geometries_of(2),
+            vec![rect(0, 0, 50, 100), rect(50, 0, 50, 100)]
+        );
+        // Earlier columns have fewer windows.
+        assert_eq!(
+            geometries_of(3),
+            vec![
+                rect(0, 0, 50, 100),
+                rect(50, 0, 50, 50),
+                rect(50, 50, 50, 50)
+            ]
+        );
+        assert_eq!(
+            geometries_of(7),
+            vec![
+                rect(0, 0, 34, 50),
+                rect(0, 50, 34, 50),
+                rect(34, 0, 33, 50),
+                rect(34, 50, 33, 50),
+                rect(67, 0, 33, 34),
+                rect(67, 34, 33, 33),
+                rect(67, 67, 33, 33),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spiral() {
+        let geometries_of = |spiral: bool, n: u64| {
+            let spiral = LayoutNode::from(LayoutSpiral::new(0.5, 0.1, spiral));
+            geometries(&layout(vec![spiral], n).0)
+        };
+
+        for spiral in [true, false] {
+            assert_eq!(geometries_of(spiral, 0), vec![]);
+            assert_eq!(geometries_of(spiral, 1), vec![rect(0, 0, 100, 100)]);
+            assert_eq!(
+                geometries_of(spiral, 2),
+                vec![rect(0, 0, 50, 100), rect(50, 0, 50, 100)]
+            );
+            assert_eq!(
+                geometries_of(spiral, 3),
+                vec![
+                    rect(0, 0, 50, 100),
+                    rect(50, 0, 50, 50),
+                    rect(50, 50, 50, 50)
+                ]
+            );
+        }
+
+        // Goes around clockwise. 25 * 0.5 is rounded up.
+        assert_eq!(
+            geometries_of(true, 6),
+            vec![
+                rect(0, 0, 50, 100),
+                rect(50, 0, 50, 50),
+                rect(75, 50, 25, 50),
+                rect(50, 75, 25, 25),
+                rect(50, 50, 13, 25),
+                rect(63, 50, 12, 25),
+            ]
+        );
+        // Goes toward bottom right.
+        assert_eq!(
+            geometries_of(false, 6),
+            vec![
+                rect(0, 0, 50, 100),
+                rect(50, 0, 50, 50),
+                rect(50, 50, 25, 50),
+                rect(75, 50, 25, 25),
+                rect(75, 75, 13, 25),
+                rect(88, 75, 12, 25),
+            ]
+        );
+    }
 }
@@ -0,0 +1,63 @@
+//! Named scratchpads, like xmonad's `NamedScratchpad`.
+
+use crate::view::window::WindowQuery;
+use smithay::utils::{Logical, Rectangle};
+use std::sync::Arc;
+
+/// A window that is toggled by `ActionScratchpadToggle`.
+///
+/// A new window matching `matcher` is adopted as the scratchpad if it has no window yet.
+#[derive(Clone)]
+pub struct Scratchpad {
+    pub name: String,
+    /// Shell command to spawn the window.
+    pub command: String,
+    pub matcher: WindowMatcher,
+    /// Geometry of the float window relative to the focused screen.
+    pub geometry: RationalRect,
+}
+
+#[derive(Clone)]
+pub enum WindowMatcher {
+    AppId(String),
+    Title(String),
+    /// Matches if the command line of the process, joined by spaces, contains the string.
+    Cmdline(String),
+    Custom(Arc<dyn Fn(&WindowQuery) -> bool>),
+}
+
+impl WindowMatcher {
+    pub fn matches(&self, wq: &WindowQuery) -> bool {
+        match self {
+            WindowMatcher::AppId(app_id) => wq.app_id().as_ref() == Some(app_id),
+            WindowMatcher::Title(title) => wq.title().as_ref() == Some(title),
+            WindowMatcher::Cmdline(s) => wq
+                .get_proc_cmdline()
+                .is_ok_and(|cmdline| cmdline.join(" ").contains(s.as_str())),
+            WindowMatcher::Custom(f) => f(wq),
+        }
+    }
+}
+
+/// Rectangle in the ratio to a rectangle, e.g. `RationalRect { x: 0.1, y: 0.1, w: 0.8, h: 0.8 }` for the center.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RationalRect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl RationalRect {
+    pub fn apply(&self, rect: &Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let scale = |v: f32, len: i32| (v * len as f32).round() as i32;
+        Rectangle::new(
+            (
+                rect.loc.x + scale(self.x, rect.size.w),
+                rect.loc.y + scale(self.y, rect.size.h),
+            )
+                .into(),
+            (scale(self.w, rect.size.w), scale(self.h, rect.size.h)).into(),
+        )
+    }
+}
@@ -1,4 +1,5 @@
 use crate::util::{FocusedVec, Id, NonEmptyFocusedVec};
+use crate::view::api::ViewDescribeApi;
 use crate::view::layout_node::{LayoutTree, LayoutTreeBuilder};
 use crate::view::window::Window;
 use smithay::utils::{Logical, Rectangle};
@@ -6,18 +7,62 @@ use std::cell::UnsafeCell;
 
 pub struct StackSet {
     pub workspaces: NonEmptyFocusedVec<Workspace>,
+    // Visible workspaces, xmonad-style. Empty until an output is added.
+    //
+    // Invariants:
+    //
+    // - `Screen::workspace_index` are distinct.
+    // - `screens.focus().workspace_index == workspaces.focused_index()` after `StackSet::sync_screens()`.
+    pub screens: FocusedVec<Screen>,
     // Bottom to top (because grab removes/inserts the top element and we make it O(1)).
     pub float_windows: Vec<FloatWindow>,
     pub window_focus_type: WindowFocusType,
+    // Internal workspace for hidden windows, e.g. scratchpads. They are not shown until floated again.
+    pub hidden_windows: Vec<Id<Window>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WorkspaceTag(pub String);
 
+#[derive(Debug, thiserror::Error)]
+pub enum WorkspaceError {
+    #[error("no workspace with tag {0:?}")]
+    NotFound(String),
+    #[error("workspace with tag {0:?} already exists")]
+    AlreadyExists(String),
+    #[error("workspace {0:?} is not empty")]
+    NotEmpty(String),
+    #[error("workspace {0:?} is visible and no hidden workspace can replace it")]
+    NoReplacement(String),
+    #[error("the last workspace can't be deleted")]
+    LastWorkspace,
+}
+
+/// Validated operations of `StackSet::delete_workspace()`.
+struct DeleteWorkspacePlan {
+    // Index of the workspace to delete.
+    index: usize,
+    // Index of the workspace to move windows to.
+    dst: Option<usize>,
+    // (Index of the screen, index of the workspace to show on it instead).
+    replacement: Option<(usize, usize)>,
+}
+
 pub struct Workspace {
     pub tag: WorkspaceTag,
     pub stack: FocusedVec<Id<Window>>,
+    // Windows taken out of the layout by `StackSet::minimize_window()`, oldest first.
+    pub minimized: Vec<MinimizedWindow>,
     layout_tree: UnsafeCell<LayoutTree>,
+    // Initial state of `layout_tree`.
+    layout_tree_builder: LayoutTreeBuilder,
+}
+
+pub struct Screen {
+    pub output: smithay::output::Output,
+    // Area for tiling, i.e. the output geometry excluding exclusive zones of layer surfaces.
+    pub rect: Rectangle<i32, Logical>,
+    pub workspace_index: usize,
 }
 
 pub struct FloatWindow {
@@ -25,6 +70,12 @@ pub struct FloatWindow {
     pub geometry: Rectangle<i32, Logical>,
 }
 
+pub struct MinimizedWindow {
+    pub id: Id<Window>,
+    // Geometry to restore the window with if it was a float window.
+    pub float_geometry: Option<Rectangle<i32, Logical>>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WindowFocusType {
     // `StackSet::workspaces.focus().stack.focus()` is focused if `Some`.
@@ -34,20 +85,24 @@ pub enum WindowFocusType {
 }
 
 impl StackSet {
-    pub(super) fn new(tags: Vec<WorkspaceTag>, layout_tree_builder: LayoutTreeBuilder) -> Self {
-        let workspaces = tags
+    pub(super) fn new(workspaces: Vec<(WorkspaceTag, LayoutTreeBuilder)>) -> Self {
+        let workspaces = workspaces
             .into_iter()
-            .map(|tag| Workspace {
+            .map(|(tag, layout_tree_builder)| Workspace {
                 tag,
                 stack: FocusedVec::default(),
+                minimized: vec![],
                 layout_tree: UnsafeCell::new(layout_tree_builder.clone().build()),
+                layout_tree_builder,
             })
             .collect();
         let workspaces = NonEmptyFocusedVec::new(workspaces, 0);
         Self {
             workspaces,
+            screens: FocusedVec::default(),
             float_windows: vec![],
             window_focus_type: WindowFocusType::Stack,
+            hidden_windows: vec![],
         }
     }
 
@@ -55,6 +110,349 @@ impl StackSet {
         &self.workspaces
     }
 
+    pub fn screens(&self) -> &FocusedVec<Screen> {
+        &self.screens
+    }
+
+    pub fn workspace_index(&self, tag: &WorkspaceTag) -> Result<usize, WorkspaceError> {
+        self.workspaces
+            .as_vec()
+            .iter()
+            .position(|ws| ws.tag == *tag)
+            .ok_or_else(|| WorkspaceError::NotFound(tag.0.clone()))
+    }
+
+    /// Adds a workspace at the end. Returns its index.
+    pub fn add_workspace(
+        &mut self,
+        tag: WorkspaceTag,
+        layout_tree_builder: LayoutTreeBuilder,
+    ) -> Result<usize, WorkspaceError> {
+        self.check_add_workspace(&tag)?;
+
+        self.workspaces.push(Workspace {
+            tag,
+            stack: FocusedVec::default(),
+            minimized: vec![],
+            layout_tree: UnsafeCell::new(layout_tree_builder.clone().build()),
+            layout_tree_builder,
+        });
+        Ok(self.workspaces.len() - 1)
+    }
+
+    /// Returns the error that `add_workspace()` would return, without modifying self.
+    pub fn check_add_workspace(&self, tag: &WorkspaceTag) -> Result<(), WorkspaceError> {
+        if self.workspace_index(tag).is_ok() {
+            return Err(WorkspaceError::AlreadyExists(tag.0.clone()));
+        }
+        Ok(())
+    }
+
+    pub fn rename_workspace(
+        &mut self,
+        tag: &WorkspaceTag,
+        new_tag: WorkspaceTag,
+    ) -> Result<(), WorkspaceError> {
+        self.check_rename_workspace(tag, &new_tag)?;
+        let i = self.workspace_index(tag)?;
+
+        let mut workspaces = self.workspaces.as_mut();
+        workspaces.vec[i].tag = new_tag;
+        workspaces.commit();
+        Ok(())
+    }
+
+    /// Returns the error that `rename_workspace()` would return, without modifying self.
+    pub fn check_rename_workspace(
+        &self,
+        tag: &WorkspaceTag,
+        new_tag: &WorkspaceTag,
+    ) -> Result<(), WorkspaceError> {
+        self.workspace_index(tag)?;
+        if tag != new_tag && self.workspace_index(new_tag).is_ok() {
+            return Err(WorkspaceError::AlreadyExists(new_tag.0.clone()));
+        }
+        Ok(())
+    }
+
+    /// Deletes a workspace.
+    ///
+    /// Windows in it are moved to `move_windows_to` if given. Otherwise, the workspace must be empty. If the workspace
+    /// is visible, a hidden workspace is shown on the screen instead.
+    pub fn delete_workspace(
+        &mut self,
+        tag: &WorkspaceTag,
+        move_windows_to: Option<&WorkspaceTag>,
+    ) -> Result<(), WorkspaceError> {
+        let DeleteWorkspacePlan {
+            index: i,
+            dst,
+            replacement,
+        } = self.plan_delete_workspace(tag, move_windows_to)?;
+
+        let mut workspaces = self.workspaces.as_mut();
+        if let Some(dst) = dst {
+            let windows = std::mem::take(&mut workspaces.vec[i].stack);
+            let minimized = std::mem::take(&mut workspaces.vec[i].minimized);
+            workspaces.vec[dst].minimized.extend(minimized);
+            let mut dst = workspaces.vec[dst].stack.as_mut();
+            dst.vec.extend(windows.as_vec());
+            dst.commit();
+        }
+        if let Some((j, k)) = replacement {
+            let mut screens = self.screens.as_mut();
+            screens.vec[j].workspace_index = k;
+            screens.commit();
+        }
+        if workspaces.focus == i {
+            workspaces.focus = match self.screens.focus() {
+                Some(screen) => screen.workspace_index,
+                None => dst.unwrap_or(if i == 0 { 1 } else { i - 1 }),
+            };
+        }
+        workspaces.vec.remove(i);
+        if workspaces.focus > i {
+            workspaces.focus -= 1;
+        }
+        workspaces.commit();
+
+        self.remap_workspace_indices(|x| if x > i { x - 1 } else { x });
+        Ok(())
+    }
+
+    /// Returns the error that `delete_workspace()` would return, without modifying self.
+    pub fn check_delete_workspace(
+        &self,
+        tag: &WorkspaceTag,
+        move_windows_to: Option<&WorkspaceTag>,
+    ) -> Result<(), WorkspaceError> {
+        self.plan_delete_workspace(tag, move_windows_to).map(|_| ())
+    }
+
+    fn plan_delete_workspace(
+        &self,
+        tag: &WorkspaceTag,
+        move_windows_to: Option<&WorkspaceTag>,
+    ) -> Result<DeleteWorkspacePlan, WorkspaceError> {
+        let i = self.workspace_index(tag)?;
+        if self.workspaces.len() == 1 {
+            return Err(WorkspaceError::LastWorkspace);
+        }
+        let dst = move_windows_to
+            .filter(|dst_tag| *dst_tag != tag)
+            .map(|dst_tag| self.workspace_index(dst_tag))
+            .transpose()?;
+        let workspace = &self.workspaces.as_vec()[i];
+        if dst.is_none() && !workspace.is_empty() {
+            return Err(WorkspaceError::NotEmpty(tag.0.clone()));
+        }
+        let replacement = match self.screen_index_of_workspace(i) {
+            Some(j) => {
+                let k = (0..self.workspaces.len())
+                    .find(|&k| k != i && !self.is_workspace_visible(k))
+                    .ok_or_else(|| WorkspaceError::NoReplacement(tag.0.clone()))?;
+                Some((j, k))
+            }
+            None => None,
+        };
+
+        Ok(DeleteWorkspacePlan {
+            index: i,
+            dst,
+            replacement,
+        })
+    }
+
+    /// Moves a workspace to `index`, shifting the others.
+    pub fn move_workspace(
+        &mut self,
+        tag: &WorkspaceTag,
+        index: usize,
+    ) -> Result<(), WorkspaceError> {
+        let i = self.workspace_index(tag)?;
+        let index = index.min(self.workspaces.len() - 1);
+
+        let remap = |x: usize| match x {
+            x if x == i => index,
+            x if i < x && x <= index => x - 1,
+            x if index <= x && x < i => x + 1,
+            x => x,
+        };
+        let mut workspaces = self.workspaces.as_mut();
+        let workspace = workspaces.vec.remove(i);
+        workspaces.vec.insert(index, workspace);
+        workspaces.focus = remap(workspaces.focus);
+        workspaces.commit();
+
+        self.remap_workspace_indices(remap);
+        Ok(())
+    }
+
+    fn remap_workspace_indices(&mut self, f: impl Fn(usize) -> usize) {
+        let mut screens = self.screens.as_mut();
+        for screen in screens.vec.iter_mut() {
+            screen.workspace_index = f(screen.workspace_index);
+        }
+        screens.commit();
+    }
+
+    /// Returns the index of the screen that shows the `i`-th workspace, if it is visible.
+    pub fn screen_index_of_workspace(&self, i: usize) -> Option<usize> {
+        self.screens
+            .as_vec()
+            .iter()
+            .position(|screen| screen.workspace_index == i)
+    }
+
+    pub fn is_workspace_visible(&self, i: usize) -> bool {
+        self.screen_index_of_workspace(i).is_some()
+    }
+
+    /// Returns true if the `i`-th workspace is neither visible nor has windows, including minimized ones.
+    ///
+    /// Float windows and hidden windows, e.g. scratchpads, don't belong to any workspace and thus are not counted.
+    pub fn is_workspace_unused(&self, i: usize) -> bool {
+        self.workspaces.as_vec()[i].is_empty() && !self.is_workspace_visible(i)
+    }
+
+    /// Focuses the `i`-th workspace (xmonad's `view`).
+    ///
+    /// If the workspace is visible on another screen, focus moves to that screen. Otherwise, the workspace is shown on
+    /// the focused screen.
+    pub fn view(&mut self, i: usize) {
+        self.workspaces.set_focused_index(i);
+        self.sync_screens();
+    }
+
+    /// Focuses the `i`-th workspace on the focused screen (xmonad's `greedyView`).
+    ///
+    /// If the workspace is visible on another screen, workspaces of the two screens are swapped.
+    pub fn greedy_view(&mut self, i: usize) {
+        if self.screens.is_empty() {
+            self.view(i);
+            return;
+        }
+
+        if let Some(j) = self.screen_index_of_workspace(i) {
+            let k = self.screens.focused_index();
+            let mut screens = self.screens.as_mut();
+            screens.vec[j].workspace_index = screens.vec[k].workspace_index;
+            screens.vec[k].workspace_index = i;
+            screens.commit();
+        }
+        self.view(i);
+    }
+
+    /// Focuses the `i`-th screen and its workspace.
+    pub fn focus_screen(&mut self, i: usize) {
+        self.screens.set_focused_index(i);
+        let workspace_index = self.screens.focus().unwrap().workspace_index;
+        self.workspaces.set_focused_index(workspace_index);
+    }
+
+    /// Reflects `workspaces.focused_index()` to screens with `view` semantics.
+    ///
+    /// `StackSet::workspaces` is public and its focus may be changed directly, e.g. by actions or a manage hook. This
+    /// recovers the invariant of `StackSet::screens`.
+    pub(crate) fn sync_screens(&mut self) {
+        let i = self.workspaces.focused_index();
+        let Some(screen) = self.screens.focus() else {
+            return;
+        };
+        if screen.workspace_index == i {
+            return;
+        }
+
+        match self.screen_index_of_workspace(i) {
+            Some(j) => self.screens.set_focused_index(j),
+            None => self.screens.focus_mut().unwrap().workspace_index = i,
+        }
+    }
+
+    pub(crate) fn add_screen(
+        &mut self,
+        output: smithay::output::Output,
+        rect: Rectangle<i32, Logical>,
+    ) {
+        // Show the focused workspace if this is the first screen. Otherwise, show the first hidden workspace.
+        let workspace_index = if self.screens.is_empty() {
+            Some(self.workspaces.focused_index())
+        } else {
+            (0..self.workspaces.len()).find(|&i| !self.is_workspace_visible(i))
+        };
+        let workspace_index = match workspace_index {
+            Some(i) => i,
+            None => {
+                // Every output needs a workspace to show. Add an empty one named after the output, with the initial
+                // layout of the focused workspace.
+                let name = output.name();
+                let tag = (1..)
+                    .map(|k| match k {
+                        1 => WorkspaceTag(name.clone()),
+                        k => WorkspaceTag(format!("{name}-{k}")),
+                    })
+                    .find(|tag| self.workspace_index(tag).is_err())
+                    .unwrap();
+                info!(
+                    "no hidden workspace is available for a new screen, adding workspace {:?}: output = {}",
+                    tag.0, name
+                );
+                let layout_tree_builder = self.workspaces.focus().layout_tree_builder.clone();
+                self.add_workspace(tag, layout_tree_builder)
+                    .unwrap(/* The tag is not used. */)
+            }
+        };
+
+        self.screens.push(Screen {
+            output,
+            rect,
+            workspace_index,
+        });
+    }
+
+    pub(crate) fn remove_screen(&mut self, output: &smithay::output::Output) {
+        let Some(i) = self
+            .screens
+            .as_vec()
+            .iter()
+            .position(|screen| screen.output == *output)
+        else {
+            return;
+        };
+
+        let mut screens = self.screens.as_mut();
+        let was_focused = screens.focus == i;
+        screens.vec.remove(i);
+        if was_focused || screens.focus > i {
+            screens.focus = screens.focus.saturating_sub(1);
+        }
+        screens.focus = screens.focus.min(screens.vec.len().saturating_sub(1));
+        screens.commit();
+
+        if let Some(screen) = self.screens.focus() {
+            self.workspaces.set_focused_index(screen.workspace_index);
+        }
+    }
+
+    // Returns true iff the rect is changed.
+    pub(crate) fn update_screen_rect(
+        &mut self,
+        output: &smithay::output::Output,
+        rect: Rectangle<i32, Logical>,
+    ) -> bool {
+        let mut screens = self.screens.as_mut();
+        let Some(screen) = screens
+            .vec
+            .iter_mut()
+            .find(|screen| screen.output == *output)
+        else {
+            return false;
+        };
+        let changed = screen.rect != rect;
+        screen.rect = rect;
+        changed
+    }
+
     pub fn set_focus(&mut self, window_id: Id<Window>) {
         let i = self.float_windows.iter().position(|x| x.id == window_id);
         if let Some(i) = i {
@@ -89,6 +487,11 @@ impl StackSet {
         let workspaces = self.workspaces.as_mut();
 
         for workspace in workspaces.vec.iter_mut() {
+            if let Some(i) = workspace.minimized.iter().position(|mw| mw.id == window_id) {
+                workspace.minimized.remove(i);
+                return;
+            }
+
             let mut stack = workspace.stack.as_mut();
             if let Some(i) = stack.vec.iter().position(|&wid| wid == window_id) {
                 stack.vec.remove(i);
@@ -102,6 +505,113 @@ impl StackSet {
         if let Some(i) = self.float_windows.iter().position(|fw| fw.id == window_id) {
             self.float_windows.remove(i);
         }
+
+        self.hidden_windows.retain(|&wid| wid != window_id);
+    }
+
+    /// Moves the window to the hidden internal workspace.
+    pub fn hide_window(&mut self, window_id: Id<Window>) {
+        self.delete_window(window_id);
+        if self.window_focus_type == WindowFocusType::Float && self.float_windows.is_empty() {
+            self.window_focus_type = WindowFocusType::Stack;
+        }
+        self.hidden_windows.push(window_id);
+    }
+
+    pub fn is_window_hidden(&self, window_id: Id<Window>) -> bool {
+        self.hidden_windows.contains(&window_id)
+    }
+
+    /// Minimizes the window, i.e. takes it out of the layout until it is restored. The window is kept alive.
+    ///
+    /// A window in a stack is kept by the workspace of the stack, and a float window by the focused workspace. Returns
+    /// false if the window is neither in a stack nor floating.
+    pub fn minimize_window(&mut self, window_id: Id<Window>) -> bool {
+        let i = self
+            .workspaces
+            .as_vec()
+            .iter()
+            .position(|ws| ws.stack.as_vec().contains(&window_id));
+        let float_geometry = self
+            .float_windows
+            .iter()
+            .find(|fw| fw.id == window_id)
+            .map(|fw| fw.geometry);
+        let i = match (i, float_geometry) {
+            (Some(i), _) => i,
+            (None, Some(_)) => self.workspaces.focused_index(),
+            (None, None) => return false,
+        };
+
+        self.delete_window(window_id);
+        if self.window_focus_type == WindowFocusType::Float && self.float_windows.is_empty() {
+            self.window_focus_type = WindowFocusType::Stack;
+        }
+        self.workspaces.as_mut().vec[i]
+            .minimized
+            .push(MinimizedWindow {
+                id: window_id,
+                float_geometry,
+            });
+        true
+    }
+
+    /// Restores the minimized window and focuses it. Returns false if the window is not minimized.
+    ///
+    /// A window that was in a stack is inserted after the focused window of the workspace that keeps it.
+    pub fn restore_window(&mut self, window_id: Id<Window>) -> bool {
+        let indice = self
+            .workspaces
+            .as_vec()
+            .iter()
+            .enumerate()
+            .find_map(|(i, ws)| {
+                let j = ws.minimized.iter().position(|mw| mw.id == window_id)?;
+                Some((i, j))
+            });
+        let Some((i, j)) = indice else {
+            return false;
+        };
+
+        let mut workspaces = self.workspaces.as_mut();
+        let workspace = &mut workspaces.vec[i];
+        let mw = workspace.minimized.remove(j);
+        match mw.float_geometry {
+            Some(geometry) => {
+                self.float_windows.push(FloatWindow {
+                    id: window_id,
+                    geometry,
+                });
+            }
+            None => {
+                let mut stack = workspace.stack.as_mut();
+                let k = if stack.vec.is_empty() {
+                    0
+                } else {
+                    stack.focus + 1
+                };
+                stack.vec.insert(k, window_id);
+                stack.commit();
+            }
+        }
+        workspaces.commit();
+
+        self.set_focus(window_id);
+        true
+    }
+
+    /// Restores the most recently minimized window of the focused workspace. Returns it if exists.
+    pub fn restore_last_window(&mut self) -> Option<Id<Window>> {
+        let window_id = self.workspaces.focus().minimized.last()?.id;
+        self.restore_window(window_id);
+        Some(window_id)
+    }
+
+    pub fn is_window_minimized(&self, window_id: Id<Window>) -> bool {
+        self.workspaces
+            .as_vec()
+            .iter()
+            .any(|ws| ws.minimized.iter().any(|mw| mw.id == window_id))
     }
 
     pub(crate) fn make_window_float(
@@ -144,6 +654,14 @@ impl StackSet {
             let workspaces = self.workspaces.as_mut();
 
             for workspace in workspaces.vec.iter_mut() {
+                if let Some(i) = workspace.minimized.iter().position(|mw| mw.id == window_id) {
+                    workspace.minimized.remove(i);
+                    return Some(FloatWindow {
+                        id: window_id,
+                        geometry: rect,
+                    });
+                }
+
                 let mut stack = workspace.stack.as_mut();
                 if let Some(i) = stack.vec.iter().position(|&wid| wid == window_id) {
                     stack.vec.remove(i);
@@ -161,6 +679,14 @@ impl StackSet {
                 return Some(self.float_windows.remove(i));
             }
 
+            if let Some(i) = self.hidden_windows.iter().position(|&wid| wid == window_id) {
+                self.hidden_windows.remove(i);
+                return Some(FloatWindow {
+                    id: window_id,
+                    geometry: rect,
+                });
+            }
+
             None
         })();
         let fw = fw.unwrap();
@@ -175,8 +701,378 @@ impl Workspace {
         &self.stack
     }
 
+    /// Returns true if the workspace has no window, including minimized ones.
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty() && self.minimized.is_empty()
+    }
+
+    /// Returns the description of the current layout, e.g. "Tall".
+    pub fn layout_description(&self) -> String {
+        // Safety: `LayoutTree` is not borrowed mutably while describing.
+        let layout_tree = unsafe { self.borrow_layout_tree() };
+        ViewDescribeApi { layout_tree }.describe_root()
+    }
+
+    /// Resets the layout tree to the initial state.
+    pub fn reset_layout_tree(&mut self) {
+        self.layout_tree = UnsafeCell::new(self.layout_tree_builder.clone().build());
+    }
+
     #[allow(clippy::mut_from_ref)]
     pub(super) unsafe fn borrow_layout_tree(&self) -> &mut LayoutTree {
         &mut *self.layout_tree.get()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::layout_node::LayoutNode;
+    use crate::view::predefined::LayoutFull;
+    use smithay::output::{Output, PhysicalProperties, Subpixel};
+    use std::collections::HashMap;
+
+    fn stackset(tags: &[&str]) -> StackSet {
+        let node = LayoutNode::from(LayoutFull {});
+        let root_id = node.id();
+        let builder = LayoutTreeBuilder::new(HashMap::from([(root_id, node)]), root_id);
+        StackSet::new(
+            tags.iter()
+                .map(|tag| (WorkspaceTag(tag.to_string()), builder.clone()))
+                .collect(),
+        )
+    }
+
+    fn output(name: &str) -> Output {
+        Output::new(
+            name.to_string(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "test".into(),
+                model: "test".into(),
+            },
+        )
+    }
+
+    fn tag(tag: &str) -> WorkspaceTag {
+        WorkspaceTag(tag.to_string())
+    }
+
+    fn tags(stackset: &StackSet) -> Vec<&str> {
+        stackset
+            .workspaces
+            .as_vec()
+            .iter()
+            .map(|ws| ws.tag.0.as_str())
+            .collect()
+    }
+
+    fn focused_tag(stackset: &StackSet) -> &str {
+        &stackset.workspaces.focus().tag.0
+    }
+
+    fn screen_tags(stackset: &StackSet) -> Vec<&str> {
+        stackset
+            .screens
+            .as_vec()
+            .iter()
+            .map(|screen| {
+                stackset.workspaces.as_vec()[screen.workspace_index]
+                    .tag
+                    .0
+                    .as_str()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_delete_workspace() {
+        let mut stackset = stackset(&["1", "2", "3"]);
+        assert!(matches!(
+            stackset.delete_workspace(&tag("4"), None),
+            Err(WorkspaceError::NotFound(_))
+        ));
+
+        stackset.workspaces.set_focused_index(2);
+        stackset.delete_workspace(&tag("2"), None).unwrap();
+        assert_eq!(tags(&stackset), vec!["1", "3"]);
+        assert_eq!(focused_tag(&stackset), "3");
+
+        stackset.delete_workspace(&tag("3"), None).unwrap();
+        assert_eq!(tags(&stackset), vec!["1"]);
+        assert_eq!(focused_tag(&stackset), "1");
+
+        assert!(matches!(
+            stackset.check_delete_workspace(&tag("1"), None),
+            Err(WorkspaceError::LastWorkspace)
+        ));
+        assert!(matches!(
+            stackset.delete_workspace(&tag("1"), None),
+            Err(WorkspaceError::LastWorkspace)
+        ));
+    }
+
+    #[test]
+    fn test_delete_workspace_with_windows() {
+        let mut stackset = stackset(&["1", "2", "3"]);
+        stackset.workspaces.focus_mut().stack.push(Id::from(1));
+        stackset.workspaces.focus_mut().stack.push(Id::from(2));
+
+        assert!(matches!(
+            stackset.check_delete_workspace(&tag("1"), None),
+            Err(WorkspaceError::NotEmpty(_))
+        ));
+        assert!(matches!(
+            stackset.delete_workspace(&tag("1"), None),
+            Err(WorkspaceError::NotEmpty(_))
+        ));
+        assert!(matches!(
+            stackset.delete_workspace(&tag("1"), Some(&tag("4"))),
+            Err(WorkspaceError::NotFound(_))
+        ));
+        assert_eq!(tags(&stackset), vec!["1", "2", "3"]);
+
+        stackset
+            .check_delete_workspace(&tag("1"), Some(&tag("3")))
+            .unwrap();
+        stackset
+            .delete_workspace(&tag("1"), Some(&tag("3")))
+            .unwrap();
+        assert_eq!(tags(&stackset), vec!["2", "3"]);
+        assert_eq!(focused_tag(&stackset), "3");
+        assert_eq!(
+            stackset.workspaces.focus().stack.as_vec(),
+            &vec![Id::from(1), Id::from(2)]
+        );
+    }
+
+    #[test]
+    fn test_delete_visible_workspace() {
+        let mut stackset = stackset(&["1", "2", "3"]);
+        stackset.add_screen(
+            output("A"),
+            Rectangle::new((0, 0).into(), (100, 100).into()),
+        );
+        stackset.add_screen(
+            output("B"),
+            Rectangle::new((0, 0).into(), (100, 100).into()),
+        );
+        assert_eq!(screen_tags(&stackset), vec!["1", "2"]);
+
+        // A hidden workspace is shown instead.
+        stackset.delete_workspace(&tag("2"), None).unwrap();
+        assert_eq!(tags(&stackset), vec!["1", "3"]);
+        assert_eq!(screen_tags(&stackset), vec!["1", "3"]);
+
+        assert!(matches!(
+            stackset.check_delete_workspace(&tag("3"), None),
+            Err(WorkspaceError::NoReplacement(_))
+        ));
+        assert!(matches!(
+            stackset.delete_workspace(&tag("3"), None),
+            Err(WorkspaceError::NoReplacement(_))
+        ));
+        assert_eq!(tags(&stackset), vec!["1", "3"]);
+        assert_eq!(screen_tags(&stackset), vec!["1", "3"]);
+    }
+
+    #[test]
+    fn test_move_workspace() {
+        let mut stackset = stackset(&["1", "2", "3", "4"]);
+        stackset.workspaces.set_focused_index(1);
+        stackset.add_screen(
+            output("A"),
+            Rectangle::new((0, 0).into(), (100, 100).into()),
+        );
+        assert!(matches!(
+            stackset.move_workspace(&tag("5"), 0),
+            Err(WorkspaceError::NotFound(_))
+        ));
+
+        stackset.move_workspace(&tag("2"), 3).unwrap();
+        assert_eq!(tags(&stackset), vec!["1", "3", "4", "2"]);
+        assert_eq!(focused_tag(&stackset), "2");
+        assert_eq!(screen_tags(&stackset), vec!["2"]);
+
+        stackset.move_workspace(&tag("4"), 0).unwrap();
+        assert_eq!(tags(&stackset), vec!["4", "1", "3", "2"]);
+        assert_eq!(focused_tag(&stackset), "2");
+        assert_eq!(screen_tags(&stackset), vec!["2"]);
+
+        // The index is clamped.
+        stackset.move_workspace(&tag("1"), 100).unwrap();
+        assert_eq!(tags(&stackset), vec!["4", "3", "2", "1"]);
+        assert_eq!(focused_tag(&stackset), "2");
+        assert_eq!(screen_tags(&stackset), vec!["2"]);
+    }
+
+    #[test]
+    fn test_is_workspace_unused() {
+        let mut stackset = stackset(&["1", "2", "3", "4"]);
+        stackset.workspaces.set_focused_index(1);
+        stackset.workspaces.focus_mut().stack.push(Id::from(1));
+        stackset.workspaces.set_focused_index(2);
+        stackset.workspaces.focus_mut().stack.push(Id::from(2));
+        assert!(stackset.minimize_window(Id::from(2)));
+        stackset.hide_window(Id::from(3));
+        stackset.workspaces.set_focused_index(0);
+        stackset.add_screen(
+            output("A"),
+            Rectangle::new((0, 0).into(), (100, 100).into()),
+        );
+        assert_eq!(screen_tags(&stackset), vec!["1"]);
+
+        // Visible.
+        assert!(!stackset.is_workspace_unused(0));
+        // Has a window.
+        assert!(!stackset.is_workspace_unused(1));
+        // Has a minimized window.
+        assert!(stackset.workspaces.as_vec()[2].stack.is_empty());
+        assert!(!stackset.is_workspace_unused(2));
+        // Hidden windows don't belong to any workspace.
+        assert!(stackset.is_window_hidden(Id::from(3)));
+        assert!(stackset.is_workspace_unused(3));
+    }
+
+    #[test]
+    fn test_add_screen_adds_workspace() {
+        let mut stackset = stackset(&["1"]);
+        stackset.add_screen(
+            output("A"),
+            Rectangle::new((0, 0).into(), (100, 100).into()),
+        );
+        assert_eq!(screen_tags(&stackset), vec!["1"]);
+
+        // No hidden workspace is available.
+        stackset.add_screen(
+            output("B"),
+            Rectangle::new((100, 0).into(), (100, 100).into()),
+        );
+        assert_eq!(tags(&stackset), vec!["1", "B"]);
+        assert_eq!(screen_tags(&stackset), vec!["1", "B"]);
+        assert_eq!(focused_tag(&stackset), "1");
+
+        stackset.add_screen(
+            output("B"),
+            Rectangle::new((200, 0).into(), (100, 100).into()),
+        );
+        assert_eq!(tags(&stackset), vec!["1", "B", "B-2"]);
+        assert_eq!(screen_tags(&stackset), vec!["1", "B", "B-2"]);
+        assert_eq!(focused_tag(&stackset), "1");
+    }
+
+    #[test]
+    fn test_remove_screen() {
+        let mut stackset = stackset(&["1", "2", "3"]);
+        let a = output("A");
+        let b = output("B");
+        stackset.add_screen(a.clone(), Rectangle::new((0, 0).into(), (100, 100).into()));
+        stackset.add_screen(
+            b.clone(),
+            Rectangle::new((100, 0).into(), (100, 100).into()),
+        );
+        stackset.focus_screen(1);
+        assert_eq!(screen_tags(&stackset), vec!["1", "2"]);
+        assert_eq!(focused_tag(&stackset), "2");
+
+        // The workspace goes back to hidden ones and the remaining screen is focused.
+        stackset.remove_screen(&b);
+        assert_eq!(tags(&stackset), vec!["1", "2", "3"]);
+        assert_eq!(screen_tags(&stackset), vec!["1"]);
+        assert_eq!(focused_tag(&stackset), "1");
+        assert!(!stackset.is_workspace_visible(1));
+
+        stackset.add_screen(
+            output("C"),
+            Rectangle::new((100, 0).into(), (100, 100).into()),
+        );
+        assert_eq!(screen_tags(&stackset), vec!["1", "2"]);
+
+        stackset.remove_screen(&a);
+        assert_eq!(screen_tags(&stackset), vec!["2"]);
+        assert_eq!(focused_tag(&stackset), "2");
+    }
+
+    #[test]
+    fn test_view_and_greedy_view() {
+        let mut stackset = stackset(&["1", "2", "3"]);
+        stackset.add_screen(
+            output("A"),
+            Rectangle::new((0, 0).into(), (100, 100).into()),
+        );
+        stackset.add_screen(
+            output("B"),
+            Rectangle::new((100, 0).into(), (100, 100).into()),
+        );
+        assert_eq!(screen_tags(&stackset), vec!["1", "2"]);
+        assert_eq!(stackset.screens.focused_index(), 0);
+
+        // `view` moves focus to the screen that shows the workspace.
+        stackset.view(1);
+        assert_eq!(screen_tags(&stackset), vec!["1", "2"]);
+        assert_eq!(stackset.screens.focused_index(), 1);
+        assert_eq!(focused_tag(&stackset), "2");
+
+        // A hidden workspace is shown on the focused screen.
+        stackset.view(2);
+        assert_eq!(screen_tags(&stackset), vec!["1", "3"]);
+        assert_eq!(stackset.screens.focused_index(), 1);
+        assert_eq!(focused_tag(&stackset), "3");
+
+        // `greedy_view` steals the workspace from the other screen, which shows the workspace of the focused one.
+        stackset.greedy_view(0);
+        assert_eq!(screen_tags(&stackset), vec!["3", "1"]);
+        assert_eq!(stackset.screens.focused_index(), 1);
+        assert_eq!(focused_tag(&stackset), "1");
+
+        stackset.greedy_view(1);
+        assert_eq!(screen_tags(&stackset), vec!["3", "2"]);
+        assert_eq!(stackset.screens.focused_index(), 1);
+        assert_eq!(focused_tag(&stackset), "2");
+    }
+
+    #[test]
+    fn test_minimize_and_restore_window() {
+        let mut stackset = stackset(&["1", "2"]);
+        for i in 0..3 {
+            stackset.workspaces.focus_mut().stack.push(Id::from(i));
+        }
+        stackset.set_focus(Id::from(1));
+        let stack = |stackset: &StackSet| stackset.workspaces.as_vec()[0].stack.as_vec().clone();
+        let focused_window =
+            |stackset: &StackSet| stackset.workspaces.focus().stack.focus().copied();
+
+        // Focus moves to the next window, or the previous one if it was the last.
+        assert!(stackset.minimize_window(Id::from(1)));
+        assert_eq!(stack(&stackset), vec![Id::from(0), Id::from(2)]);
+        assert_eq!(focused_window(&stackset), Some(Id::from(2)));
+        assert!(stackset.is_window_minimized(Id::from(1)));
+        assert!(stackset.minimize_window(Id::from(2)));
+        assert_eq!(stack(&stackset), vec![Id::from(0)]);
+        assert_eq!(focused_window(&stackset), Some(Id::from(0)));
+        assert!(!stackset.minimize_window(Id::from(9)));
+
+        // The window goes back to the workspace that keeps it, after the focused window.
+        stackset.view(1);
+        assert!(stackset.restore_window(Id::from(1)));
+        assert_eq!(focused_tag(&stackset), "1");
+        assert_eq!(stack(&stackset), vec![Id::from(0), Id::from(1)]);
+        assert_eq!(focused_window(&stackset), Some(Id::from(1)));
+        assert!(!stackset.is_window_minimized(Id::from(1)));
+
+        // Restoring a window that is not minimized is a no-op.
+        assert!(!stackset.restore_window(Id::from(1)));
+        assert!(!stackset.restore_window(Id::from(9)));
+        assert_eq!(stack(&stackset), vec![Id::from(0), Id::from(1)]);
+        assert_eq!(focused_window(&stackset), Some(Id::from(1)));
+
+        assert_eq!(stackset.restore_last_window(), Some(Id::from(2)));
+        assert_eq!(
+            stack(&stackset),
+            vec![Id::from(0), Id::from(1), Id::from(2)]
+        );
+        assert_eq!(focused_window(&stackset), Some(Id::from(2)));
+        assert_eq!(stackset.restore_last_window(), None);
+    }
+}
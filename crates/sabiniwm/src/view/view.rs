@@ -1,11 +1,13 @@
 use crate::config::{ConfigDelegate, ConfigDelegateUnstableI};
 use crate::util::{FocusedVec, Id};
 use crate::view::api::{ViewHandleMessageApi, ViewLayoutApi};
+use crate::view::decoration::{Decoration, DecorationElement};
 use crate::view::layout_node::LayoutMessage;
+use crate::view::scratchpad::Scratchpad;
 use crate::view::stackset::{FloatWindow, StackSet, WindowFocusType};
 use crate::view::window::{Border, Window, WindowProps};
 use itertools::Itertools;
-use smithay::utils::{Logical, Rectangle, Size};
+use smithay::utils::{Logical, Point, Rectangle};
 use std::collections::{HashMap, HashSet};
 
 pub(crate) struct View {
@@ -18,23 +20,45 @@ pub(super) struct ViewState {
     // TODO: Rename.
     pub(super) layout_queue: Vec<(Id<Window>, WindowProps)>,
     pub(super) windows: HashMap<Id<Window>, Window>,
-    pub(super) rect: Rectangle<i32, Logical>,
+    decorations: Vec<DecorationElement>,
+    // Windows that requested attention, e.g. via xdg-activation, while they are not focused.
+    urgent_windows: HashSet<Id<Window>>,
     // Read only. Cache it as getting it requires `ConfigDelegate`.
     border_for_float_window: Border,
+    // Read only.
+    smart_border_for_float_window: bool,
+    // Read only.
+    scratchpads: Vec<Scratchpad>,
+    // Windows of scratchpads by name.
+    scratchpad_windows: HashMap<String, Id<Window>>,
+    // See `View::generation()`.
+    generation: u64,
 }
 
 impl View {
-    pub fn new(config_delegate: &ConfigDelegate, rect: Rectangle<i32, Logical>) -> Self {
-        let workspace_tags = config_delegate.make_workspace_tags();
-        let layout_tree_builder = config_delegate.make_layout_tree_builder();
-        let stackset = StackSet::new(workspace_tags, layout_tree_builder);
+    pub fn new(config_delegate: &ConfigDelegate) -> Self {
+        let workspaces = config_delegate
+            .make_workspace_tags()
+            .into_iter()
+            .map(|tag| {
+                let layout_tree_builder =
+                    config_delegate.make_layout_tree_builder_for_workspace(&tag);
+                (tag, layout_tree_builder)
+            })
+            .collect();
+        let stackset = StackSet::new(workspaces);
 
         let state = ViewState {
             stackset,
             layout_queue: Vec::new(),
             windows: HashMap::new(),
-            rect,
+            decorations: Vec::new(),
+            urgent_windows: HashSet::new(),
             border_for_float_window: config_delegate.get_border_for_float_window(),
+            smart_border_for_float_window: config_delegate.smart_border_for_float_window(),
+            scratchpads: config_delegate.make_scratchpads(),
+            scratchpad_windows: HashMap::new(),
+            generation: 0,
         };
         Self { state }
     }
@@ -43,10 +67,27 @@ impl View {
         &self.state.stackset
     }
 
-    // Returns true iff self is changed.
-    pub fn refresh(&mut self, space: &mut smithay::desktop::Space<Window>) -> bool {
+    /// Returns a counter that changes whenever the view may have been changed.
+    ///
+    /// States derived from the view can skip recalculation while it is unchanged.
+    pub fn generation(&self) -> u64 {
+        self.state.generation
+    }
+
+    /// Marks the view changed. Call this for changes that the view can't observe, e.g. titles of windows.
+    pub fn mark_changed(&mut self) {
+        self.state.generation = self.state.generation.wrapping_add(1);
+    }
+
+    // Returns ids of removed windows. Self is changed iff it's not empty.
+    pub fn refresh(&mut self, space: &mut smithay::desktop::Space<Window>) -> Vec<Id<Window>> {
         use smithay::utils::IsAlive;
 
+        // Follow title changes. It doesn't change layout.
+        for decoration in &mut self.state.decorations {
+            decoration.refresh_label(&self.state.windows);
+        }
+
         let mut removed_window_ids = None;
         for window in self.state.windows.values() {
             if !window.alive() {
@@ -58,13 +99,16 @@ impl View {
             }
         }
         let Some(removed_window_ids) = removed_window_ids else {
-            return false;
+            return vec![];
         };
 
         let removed_windows = removed_window_ids
             .iter()
             .map(|wid| self.state.windows.remove(wid).unwrap())
             .collect_vec();
+        for wid in &removed_window_ids {
+            self.state.urgent_windows.remove(wid);
+        }
 
         // Speed: In normal use cases, we expect `removed_window_ids.len()` is very small and avoid using `HashSet`.
         //
@@ -97,11 +141,21 @@ impl View {
                 .and_then(|focus| stack.vec.iter().position(|&wid| wid == focus))
                 .unwrap_or(0);
             stack.commit();
+            workspace
+                .minimized
+                .retain(|mw| !removed_window_ids.contains(&mw.id));
         }
         self.state
             .stackset
             .float_windows
             .retain(|fw| !removed_window_ids.contains(&fw.id));
+        self.state
+            .stackset
+            .hidden_windows
+            .retain(|wid| !removed_window_ids.contains(wid));
+        self.state
+            .scratchpad_windows
+            .retain(|_, wid| !removed_window_ids.contains(wid));
 
         if self.state.stackset.window_focus_type == WindowFocusType::Float
             && self.state.stackset.float_windows.is_empty()
@@ -115,39 +169,91 @@ impl View {
 
         self.layout(space);
 
-        true
+        removed_window_ids
     }
 
     pub fn layout(&mut self, space: &mut smithay::desktop::Space<Window>) {
         assert!(self.state.layout_queue.is_empty());
 
+        self.mark_changed();
+
+        self.state.stackset.sync_screens();
+
         // Layout
-        let workspace = self.state.stackset.workspaces().focus();
-        // Safety: `LayoutTree` is not borrowed in `ViewLayoutApi`.
-        let layout_tree = unsafe { workspace.borrow_layout_tree() };
-        let rect = self.state.rect;
-        let mut api = ViewLayoutApi {
-            workspace,
-            layout_tree,
-            layout_queue: &mut self.state.layout_queue,
-            rect,
-        };
-        api.layout_node_root();
+        //
+        // Each visible workspace is laid out into the rect of its screen. The root node of each screen sees only the
+        // windows and decorations laid out for the screen.
+        let mut layout_result = vec![];
+        let mut decorations = vec![];
+        let output_geometries = self
+            .state
+            .stackset
+            .screens()
+            .as_vec()
+            .iter()
+            .map(|screen| space.output_geometry(&screen.output))
+            .collect::<Vec<_>>();
+        for (i, screen) in self.state.stackset.screens().as_vec().iter().enumerate() {
+            let workspace = &self.state.stackset.workspaces().as_vec()[screen.workspace_index];
+            // Safety: `LayoutTree` is not borrowed in `ViewLayoutApi`.
+            let layout_tree = unsafe { workspace.borrow_layout_tree() };
+            let decoration_queue_start = decorations.len();
+            let mut api = ViewLayoutApi {
+                workspace,
+                windows: &self.state.windows,
+                stack: &workspace.stack,
+                layout_tree,
+                layout_queue: &mut self.state.layout_queue,
+                decoration_queue: &mut decorations,
+                rect: screen.rect,
+                layout_queue_start: 0,
+                decoration_queue_start,
+            };
+            api.layout_node_root();
+            // Windows are not clipped to the screen. So windows partially off the screen are kept mapped only if they
+            // don't intrude on another output, and the others are unmapped below.
+            let rect = screen.rect;
+            let is_shown = |g: Rectangle<i32, Logical>| {
+                let intrudes = output_geometries[i].is_none_or(|o| !o.contains_rect(g))
+                    && output_geometries
+                        .iter()
+                        .enumerate()
+                        .any(|(j, o)| j != i && o.is_some_and(|o| o.overlaps(g)));
+                (rect.overlaps(g) || rect.contains_rect(g)) && !intrudes
+            };
+            layout_result.extend(
+                self.state
+                    .layout_queue
+                    .drain(..)
+                    .filter(|(_, props)| is_shown(props.geometry)),
+            );
+            let screen_decorations = decorations.split_off(decoration_queue_start);
+            decorations.extend(
+                screen_decorations
+                    .into_iter()
+                    .filter(|decoration| is_shown(decoration.rect)),
+            );
+        }
 
         // Remove windows from the space that are not in layout result.
         let mut removing_window_ids = space.elements().map(|w| w.id()).collect::<HashSet<_>>();
-        for (window_id, _) in &self.state.layout_queue {
+        for (window_id, _) in &layout_result {
             removing_window_ids.remove(window_id);
         }
+        for fw in &self.state.stackset.float_windows {
+            removing_window_ids.remove(&fw.id);
+        }
         for window_id in removing_window_ids {
             let window = self.state.windows.get(&window_id).unwrap();
             space.unmap_elem(window);
         }
 
-        debug!("layout_queue = {:?}", self.state.layout_queue);
+        debug!("layout_result = {:?}", layout_result);
         // Reflect layout to the space and surfaces.
-        for (window_id, props) in self.state.layout_queue.drain(..) {
+        for (window_id, mut props) in layout_result {
             let window = self.state.windows.get_mut(&window_id).unwrap();
+            // Clients may not be able to fit in the tile. Center it in the tile in that case.
+            props.geometry = window.size_hints().fit(props.geometry);
             let geometry = props.geometry;
             window.set_props(props);
             space.map_element(window.clone(), geometry.loc, false);
@@ -162,11 +268,29 @@ impl View {
 
         assert!(self.state.layout_queue.is_empty());
 
+        self.update_decorations(decorations);
+
         for fw in &self.state.stackset.float_windows {
             let window = self.state.windows.get_mut(&fw.id).unwrap();
+            let covers_screen = || {
+                self.state
+                    .stackset
+                    .screens()
+                    .as_vec()
+                    .iter()
+                    .any(|screen| fw.geometry.contains_rect(screen.rect))
+            };
+            let border = if self.state.smart_border_for_float_window && covers_screen() {
+                Border {
+                    dim: 0.into(),
+                    ..self.state.border_for_float_window.clone()
+                }
+            } else {
+                self.state.border_for_float_window.clone()
+            };
             let props = WindowProps {
                 geometry: fw.geometry,
-                border: self.state.border_for_float_window.clone(),
+                border,
             };
             window.set_props(props);
             space.map_element(window.clone(), fw.geometry.loc, false);
@@ -180,6 +304,32 @@ impl View {
         }
     }
 
+    /// Keeps buffers of unchanged decorations.
+    fn update_decorations(&mut self, decorations: Vec<Decoration>) {
+        let mut olds = std::mem::take(&mut self.state.decorations);
+        self.state.decorations = decorations
+            .into_iter()
+            .map(
+                |decoration| match olds.iter().position(|old| *old.decoration() == decoration) {
+                    Some(i) => olds.swap_remove(i),
+                    None => DecorationElement::new(decoration, &self.state.windows),
+                },
+            )
+            .collect();
+    }
+
+    pub(crate) fn decorations(&self) -> &[DecorationElement] {
+        &self.state.decorations
+    }
+
+    pub fn decoration_under(&self, pos: Point<f64, Logical>) -> Option<&Decoration> {
+        self.state
+            .decorations
+            .iter()
+            .map(|x| x.decoration())
+            .find(|decoration| decoration.rect.to_f64().contains(pos))
+    }
+
     pub fn handle_layout_message(
         &mut self,
         message: &LayoutMessage,
@@ -188,21 +338,54 @@ impl View {
         let workspace = self.state.stackset.workspaces().focus();
         // Safety: `LayoutTree` is not borrowed in `ViewLayoutApi`.
         let layout_tree = unsafe { workspace.borrow_layout_tree() };
-        let mut api = ViewHandleMessageApi { layout_tree };
+        let mut api = ViewHandleMessageApi {
+            stack: &workspace.stack,
+            layout_tree,
+        };
         api.handle_message_root(message);
 
         self.layout(space);
     }
 
-    pub fn resize_output(
+    pub fn add_output(
+        &mut self,
+        output: smithay::output::Output,
+        rect: Rectangle<i32, Logical>,
+        space: &mut smithay::desktop::Space<Window>,
+    ) {
+        self.state.stackset.add_screen(output, rect);
+        self.layout(space);
+    }
+
+    pub fn remove_output(
         &mut self,
-        size: Size<i32, Logical>,
+        output: &smithay::output::Output,
         space: &mut smithay::desktop::Space<Window>,
     ) {
-        self.state.rect = Rectangle::from_size(size);
+        self.state.stackset.remove_screen(output);
         self.layout(space);
     }
 
+    pub fn resize_output(
+        &mut self,
+        output: &smithay::output::Output,
+        rect: Rectangle<i32, Logical>,
+        space: &mut smithay::desktop::Space<Window>,
+    ) {
+        if self.state.stackset.update_screen_rect(output, rect) {
+            self.layout(space);
+        }
+    }
+
+    /// Returns the rect of the focused screen, or `None` if no output exists.
+    pub fn focused_screen_rect(&self) -> Option<Rectangle<i32, Logical>> {
+        self.state
+            .stackset
+            .screens()
+            .focus()
+            .map(|screen| screen.rect)
+    }
+
     pub fn register_window(&mut self, smithay_window: smithay::desktop::Window) -> Id<Window> {
         let window = Window::new(smithay_window);
         let window_id = window.id();
@@ -213,12 +396,14 @@ impl View {
             .stack
             .push(window_id);
         self.state.windows.insert(window_id, window);
+        self.mark_changed();
 
         window_id
     }
 
     pub fn set_focus(&mut self, window_id: Id<Window>) {
         self.state.stackset.set_focus(window_id);
+        self.mark_changed();
     }
 
     pub fn run_manage_hook(
@@ -232,8 +417,67 @@ impl View {
         self.set_focus(window_id);
 
         let window = self.state.windows.get(&window_id).unwrap().clone();
-        let wq = WindowQuery::new(window, display_handle, self.state.rect);
+        let wq = WindowQuery::new(window, display_handle, self.focused_screen_rect());
         config_delegate.run_manage_hook(&mut self.state.stackset, &wq);
+
+        // Adopt the window as a scratchpad that has no window yet.
+        let scratchpad = self.state.scratchpads.iter().find(|sp| {
+            !self.state.scratchpad_windows.contains_key(&sp.name) && sp.matcher.matches(&wq)
+        });
+        if let Some(sp) = scratchpad {
+            // Keep it hidden until toggled if no output exists.
+            match wq.get_primary_output_rect() {
+                Some(rect) => {
+                    let rect = sp.geometry.apply(rect);
+                    self.state.stackset.float_window_with_rect(window_id, rect);
+                }
+                None => self.state.stackset.hide_window(window_id),
+            }
+            self.state
+                .scratchpad_windows
+                .insert(sp.name.clone(), window_id);
+        }
+        self.mark_changed();
+    }
+
+    pub fn has_scratchpad(&self, name: &str) -> bool {
+        self.state.scratchpads.iter().any(|sp| sp.name == name)
+    }
+
+    /// Shows the scratchpad as a float window on the focused screen, or hides it if it is shown.
+    ///
+    /// Returns the command to spawn if the scratchpad has no window.
+    pub fn toggle_scratchpad(&mut self, name: &str) -> Option<String> {
+        let Some(sp) = self.state.scratchpads.iter().find(|sp| sp.name == name) else {
+            warn!("no scratchpad named {:?}", name);
+            return None;
+        };
+        let Some(&window_id) = self.state.scratchpad_windows.get(name) else {
+            return Some(sp.command.clone());
+        };
+
+        let stackset = &self.state.stackset;
+        let is_shown = stackset.float_windows.iter().any(|fw| fw.id == window_id)
+            || stackset
+                .workspaces
+                .as_vec()
+                .iter()
+                .enumerate()
+                .any(|(i, ws)| {
+                    stackset.is_workspace_visible(i) && ws.stack.as_vec().contains(&window_id)
+                });
+        if is_shown {
+            self.state.stackset.hide_window(window_id);
+        } else {
+            let Some(rect) = self.focused_screen_rect() else {
+                warn!("no output to show scratchpad {:?}", name);
+                return None;
+            };
+            let rect = sp.geometry.apply(&rect);
+            self.state.stackset.float_window_with_rect(window_id, rect);
+        }
+        self.mark_changed();
+        None
     }
 
     pub fn focused_window(&self) -> Option<&Window> {
@@ -256,13 +500,34 @@ impl View {
         self.state.windows.get(&id)
     }
 
+    pub fn windows(&self) -> impl Iterator<Item = &Window> {
+        self.state.windows.values()
+    }
+
+    pub fn is_urgent(&self, id: Id<Window>) -> bool {
+        self.state.urgent_windows.contains(&id)
+    }
+
+    pub fn set_urgent(&mut self, id: Id<Window>, urgent: bool) {
+        let changed = if urgent {
+            self.state.urgent_windows.insert(id)
+        } else {
+            self.state.urgent_windows.remove(&id)
+        };
+        if changed {
+            self.mark_changed();
+        }
+    }
+
     pub fn update_stackset_with<T>(&mut self, f: impl FnOnce(&mut StackSet) -> T) -> T {
+        self.mark_changed();
         f(&mut self.state.stackset)
     }
 
     pub fn make_window_float(&mut self, window_id: Id<Window>) {
         let geometry = self.get_window(window_id).unwrap().geometry_actual();
         self.state.stackset.make_window_float(window_id, geometry);
+        self.mark_changed();
     }
 
     pub fn update_float_window_with(&mut self, id: Id<Window>, f: impl FnOnce(&mut FloatWindow)) {
@@ -280,5 +545,6 @@ impl View {
         };
         let fw = &mut self.state.stackset.float_windows[i];
         f(fw);
+        self.mark_changed();
     }
 }
@@ -1,5 +1,6 @@
 mod props {
-    use smithay::utils::{Logical, Rectangle};
+    use crate::model::grid_geometry::RectangleExt;
+    use smithay::utils::{Logical, Rectangle, Size};
 
     #[derive(Debug, Clone, PartialEq, Eq)]
     pub struct Thickness {
@@ -94,6 +95,80 @@ mod props {
         pub geometry: Rectangle<i32, Logical>,
         pub border: Border,
     }
+
+    /// Size constraints requested by the client. Each dimension is 0 if unspecified.
+    ///
+    /// Base size, resize increments and aspect ratios are given only by X11 clients (`WM_NORMAL_HINTS`).
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub struct SizeHints {
+        pub min_size: Size<i32, Logical>,
+        pub max_size: Size<i32, Logical>,
+        pub base_size: Size<i32, Logical>,
+        pub size_increment: Size<i32, Logical>,
+        /// Minimum and maximum aspect ratios, i.e. width / height.
+        pub aspect: Option<(f64, f64)>,
+    }
+
+    impl SizeHints {
+        /// Clamps `size` to the hints. The minimum size wins if it conflicts with the maximum size.
+        ///
+        /// Like xmonad's `applySizeHints`, the aspect ratios and the increments only shrink the size above the base
+        /// size.
+        pub fn clamp(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+            fn clamp1(x: i32, min: i32, max: i32) -> i32 {
+                let x = if max > 0 { x.min(max) } else { x };
+                if min > 0 {
+                    x.max(min)
+                } else {
+                    x
+                }
+            }
+
+            let size = self.apply_aspect_and_increment(size);
+            Size::from((
+                clamp1(size.w, self.min_size.w, self.max_size.w),
+                clamp1(size.h, self.min_size.h, self.max_size.h),
+            ))
+        }
+
+        fn apply_aspect_and_increment(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+            if self.aspect.is_none() && self.size_increment == Size::default() {
+                return size;
+            }
+
+            // ICCCM: The minimum size is used as the base size if the latter is not specified.
+            let base_or_min = |base: i32, min: i32| if base > 0 { base } else { min.max(0) };
+            let base_w = base_or_min(self.base_size.w, self.min_size.w);
+            let base_h = base_or_min(self.base_size.h, self.min_size.h);
+            let mut w = (size.w - base_w).max(0);
+            let mut h = (size.h - base_h).max(0);
+
+            if let Some((min_aspect, max_aspect)) = self.aspect {
+                if max_aspect > 0.0 && w as f64 > h as f64 * max_aspect {
+                    w = (h as f64 * max_aspect).floor() as i32;
+                } else if min_aspect > 0.0 && (w as f64) < h as f64 * min_aspect {
+                    h = (w as f64 / min_aspect).floor() as i32;
+                }
+            }
+            if self.size_increment.w > 0 {
+                w -= w % self.size_increment.w;
+            }
+            if self.size_increment.h > 0 {
+                h -= h % self.size_increment.h;
+            }
+
+            Size::from((w + base_w, h + base_h))
+        }
+
+        /// Clamps the size of `rect` to the hints and centers it in `rect`.
+        pub fn fit(&self, rect: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+            let size = self.clamp(rect.size);
+            if size == rect.size {
+                return rect;
+            }
+            Rectangle::new(rect.loc, size).with_center(rect.center())
+        }
+    }
 }
 
 #[allow(clippy::module_inception)]
@@ -192,6 +267,67 @@ mod window {
             self.swindow.on_commit();
         }
 
+        /// Returns the title of xdg toplevel or X11 window.
+        pub fn title(&self) -> Option<String> {
+            use smithay::desktop::WindowSurface;
+            use smithay::wayland::shell::xdg::XdgToplevelSurfaceData;
+
+            match self.swindow.underlying_surface() {
+                WindowSurface::Wayland(s) => {
+                    smithay::wayland::compositor::with_states(s.wl_surface(), |states| {
+                        let data = states.data_map.get::<XdgToplevelSurfaceData>()?;
+                        data.lock().unwrap().title.clone()
+                    })
+                }
+                WindowSurface::X11(s) => Some(s.title()),
+            }
+        }
+
+        /// Returns size hints of xdg toplevel or X11 window.
+        pub fn size_hints(&self) -> SizeHints {
+            use smithay::desktop::WindowSurface;
+            use smithay::wayland::shell::xdg::SurfaceCachedState;
+
+            match self.swindow.underlying_surface() {
+                WindowSurface::Wayland(s) => {
+                    smithay::wayland::compositor::with_states(s.wl_surface(), |states| {
+                        let mut cached = states.cached_state.get::<SurfaceCachedState>();
+                        let current = cached.current();
+                        SizeHints {
+                            min_size: current.min_size,
+                            max_size: current.max_size,
+                            ..Default::default()
+                        }
+                    })
+                }
+                WindowSurface::X11(s) => {
+                    let hints = s.size_hints();
+                    let hints = hints.as_ref();
+                    SizeHints {
+                        min_size: s.min_size().unwrap_or_default(),
+                        max_size: s.max_size().unwrap_or_default(),
+                        base_size: hints
+                            .and_then(|h| h.base_size)
+                            .map(Size::from)
+                            .unwrap_or_default(),
+                        size_increment: hints
+                            .and_then(|h| h.size_increment)
+                            .map(Size::from)
+                            .unwrap_or_default(),
+                        aspect: hints
+                            .and_then(|h| h.aspect.as_ref())
+                            .filter(|(min, max)| min.denominator > 0 && max.denominator > 0)
+                            .map(|(min, max)| {
+                                (
+                                    min.numerator as f64 / min.denominator as f64,
+                                    max.numerator as f64 / max.denominator as f64,
+                                )
+                            }),
+                    }
+                }
+            }
+        }
+
         pub fn surface_under<P: Into<Point<f64, Logical>>>(
             &self,
             point: P,
@@ -232,6 +368,18 @@ mod window {
             self.inner.lock().unwrap().props.geometry
         }
 
+        /// Requests the client to close the window.
+        pub fn close(&self) {
+            use smithay::desktop::WindowSurface;
+
+            match self.swindow.underlying_surface() {
+                WindowSurface::Wayland(w) => w.send_close(),
+                WindowSurface::X11(w) => {
+                    let _ = w.close();
+                }
+            };
+        }
+
         fn update_ssd(&mut self) {
             use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 
@@ -470,14 +618,14 @@ mod query {
     pub struct WindowQuery {
         window: Window,
         display_handle: smithay::reexports::wayland_server::DisplayHandle,
-        rect: Rectangle<i32, Logical>,
+        rect: Option<Rectangle<i32, Logical>>,
     }
 
     impl WindowQuery {
         pub fn new(
             window: Window,
             display_handle: smithay::reexports::wayland_server::DisplayHandle,
-            rect: Rectangle<i32, Logical>,
+            rect: Option<Rectangle<i32, Logical>>,
         ) -> Self {
             Self {
                 window,
@@ -490,8 +638,9 @@ mod query {
             self.window.id()
         }
 
-        pub fn get_primary_output_rect(&self) -> &Rectangle<i32, Logical> {
-            &self.rect
+        /// Returns the rect of the focused screen, or `None` if no output exists.
+        pub fn get_primary_output_rect(&self) -> Option<&Rectangle<i32, Logical>> {
+            self.rect.as_ref()
         }
 
         pub fn app_id(&self) -> Option<String> {
@@ -509,10 +658,7 @@ mod query {
         }
 
         pub fn title(&self) -> Option<String> {
-            match self.window.smithay_window().underlying_surface() {
-                WindowSurface::Wayland(s) => with_toplevel_surface_data(s, |x| x.title.clone()),
-                WindowSurface::X11(s) => Some(s.title()),
-            }
+            self.window.title()
         }
 
         pub fn is_modal(&self) -> Option<bool> {
@@ -563,7 +709,93 @@ mod query {
 }
 
 pub(crate) use props::*;
-pub use props::{Border, Rgba, Thickness};
+pub use props::{Border, Rgba, SizeHints, Thickness};
 pub use query::WindowQuery;
 pub(crate) use window::as_render_elements::*;
 pub(crate) use window::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smithay::utils::{Logical, Rectangle};
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::new((x, y).into(), (w, h).into())
+    }
+
+    #[test]
+    fn test_size_hints_fit_min_max() {
+        assert_eq!(
+            SizeHints::default().fit(rect(0, 0, 100, 100)),
+            rect(0, 0, 100, 100)
+        );
+
+        let hints = SizeHints {
+            min_size: (50, 60).into(),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 40, 100)), rect(-5, 0, 50, 100));
+        assert_eq!(hints.fit(rect(0, 0, 100, 100)), rect(0, 0, 100, 100));
+
+        let hints = SizeHints {
+            max_size: (50, 0).into(),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 100)), rect(25, 0, 50, 100));
+        assert_eq!(hints.fit(rect(0, 0, 40, 100)), rect(0, 0, 40, 100));
+
+        // The minimum size wins.
+        let hints = SizeHints {
+            min_size: (80, 0).into(),
+            max_size: (50, 0).into(),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 100)), rect(10, 0, 80, 100));
+        assert_eq!(hints.fit(rect(0, 0, 40, 100)), rect(-20, 0, 80, 100));
+    }
+
+    #[test]
+    fn test_size_hints_fit_increment() {
+        let hints = SizeHints {
+            base_size: (10, 10).into(),
+            size_increment: (20, 30).into(),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 100)), rect(5, 0, 90, 100));
+        assert_eq!(hints.fit(rect(0, 0, 5, 5)), rect(-3, -3, 10, 10));
+
+        // The minimum size is used as the base size.
+        let hints = SizeHints {
+            min_size: (10, 10).into(),
+            size_increment: (20, 20).into(),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 100)), rect(5, 5, 90, 90));
+    }
+
+    #[test]
+    fn test_size_hints_fit_aspect() {
+        let hints = SizeHints {
+            aspect: Some((1.0, 1.0)),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 50)), rect(25, 0, 50, 50));
+        assert_eq!(hints.fit(rect(0, 0, 50, 100)), rect(0, 25, 50, 50));
+
+        let hints = SizeHints {
+            aspect: Some((0.5, 2.0)),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 100)), rect(0, 0, 100, 100));
+        assert_eq!(hints.fit(rect(0, 0, 300, 100)), rect(50, 0, 200, 100));
+        assert_eq!(hints.fit(rect(0, 0, 100, 300)), rect(0, 50, 100, 200));
+
+        // Applied before the increments.
+        let hints = SizeHints {
+            size_increment: (30, 1).into(),
+            aspect: Some((1.0, 1.0)),
+            ..Default::default()
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 50)), rect(35, 0, 30, 50));
+    }
+}
@@ -0,0 +1,61 @@
+use sabiniwm_ipc::{Action, Client, Request, Response, WorkspaceTarget};
+
+const USAGE: &str = "\
+Usage: sabiniwmctl <COMMAND>
+
+Commands:
+  stackset                 Print the current stackset
+  dispatch <ACTION_JSON>   Execute an action, e.g. '{\"type\":\"window_kill\"}'
+  spawn <COMMAND>          Spawn a command
+  workspace <TAG>          Focus the workspace with the tag
+  subscribe                Print events, one JSON per line
+  request <REQUEST_JSON>   Send a raw request
+";
+
+fn parse_args(args: &[String]) -> eyre::Result<Request> {
+    let args = args.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    let request = match args[..] {
+        ["stackset"] => Request::GetStackSet,
+        ["dispatch", action] => Request::Dispatch {
+            action: serde_json::from_str(action)?,
+        },
+        ["spawn", command] => Request::Dispatch {
+            action: Action::Spawn {
+                command: command.to_string(),
+            },
+        },
+        ["workspace", tag] => Request::Dispatch {
+            action: Action::WorkspaceFocus {
+                target: WorkspaceTarget::Tag(tag.to_string()),
+            },
+        },
+        ["request", request] => serde_json::from_str(request)?,
+        _ => eyre::bail!("invalid arguments\n\n{}", USAGE),
+    };
+    Ok(request)
+}
+
+fn main() -> eyre::Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if matches!(args.first().map(|s| s.as_str()), Some("-h" | "--help")) {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+
+    if args == ["subscribe"] {
+        for event in Client::connect()?.subscribe()? {
+            println!("{}", serde_json::to_string(&event?)?);
+        }
+        return Ok(());
+    }
+
+    let request = parse_args(&args)?;
+    let mut client = Client::connect()?;
+    let response = client.request(&request)?;
+    println!("{}", serde_json::to_string_pretty(&response)?);
+
+    if let Response::Error { .. } = response {
+        std::process::exit(1);
+    }
+    Ok(())
+}